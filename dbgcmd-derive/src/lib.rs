@@ -0,0 +1,394 @@
+//! Derive macro for the `DebugCommand` trait of the `dbgcmd` crate.
+//!
+//! You shouldn't need to depend on this crate directly. Instead, turn on the
+//! `derive` feature of `dbgcmd` and use `dbgcmd::DebugCommand`.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{parse_macro_input, Attribute, Data, DeriveInput, Field, Fields, LitChar, LitStr, Type};
+
+/// Derives `FromStr` and `dbgcmd::DebugCommand` for an enum, where each variant
+/// is a command and each of its fields is an argument.
+///
+/// See the documentation of `dbgcmd::DebugCommand` for details.
+#[proc_macro_derive(DebugCommand, attributes(command))]
+pub fn derive_debug_command(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+#[derive(Clone, Copy)]
+enum RenameRule {
+    Kebab,
+    Snake,
+    Lower,
+}
+
+impl RenameRule {
+    fn parse(lit: &LitStr) -> syn::Result<Self> {
+        match lit.value().as_str() {
+            "kebab-case" => Ok(RenameRule::Kebab),
+            "snake_case" => Ok(RenameRule::Snake),
+            "lowercase" => Ok(RenameRule::Lower),
+            _ => Err(syn::Error::new_spanned(
+                lit,
+                "expected \"kebab-case\", \"snake_case\", or \"lowercase\"",
+            )),
+        }
+    }
+
+    fn apply(self, ident: &str) -> String {
+        let separator = match self {
+            RenameRule::Kebab => "-",
+            RenameRule::Snake => "_",
+            RenameRule::Lower => "",
+        };
+        split_words(ident)
+            .iter()
+            .map(|word| word.to_lowercase())
+            .collect::<Vec<_>>()
+            .join(separator)
+    }
+}
+
+/// Splits a CamelCase identifier into its words, keeping acronyms together,
+/// so `HTTPServerId` becomes `HTTP`, `Server`, `Id`.
+fn split_words(ident: &str) -> Vec<String> {
+    let chars: Vec<char> = ident.chars().collect();
+    let mut words = Vec::new();
+    let mut word = String::new();
+
+    for (i, &ch) in chars.iter().enumerate() {
+        if ch == '_' {
+            if !word.is_empty() {
+                words.push(std::mem::take(&mut word));
+            }
+            continue;
+        }
+        if ch.is_uppercase() && !word.is_empty() {
+            let prev = chars[i - 1];
+            let next_lower = chars.get(i + 1).is_some_and(|next| next.is_lowercase());
+            if !prev.is_uppercase() || next_lower {
+                words.push(std::mem::take(&mut word));
+            }
+        }
+        word.push(ch);
+    }
+    if !word.is_empty() {
+        words.push(word);
+    }
+    words
+}
+
+/// Returns the first line of the doc comment in `attrs`, if there is one.
+fn doc_summary(attrs: &[Attribute]) -> String {
+    for attr in attrs {
+        if !attr.path().is_ident("doc") {
+            continue;
+        }
+        if let syn::Meta::NameValue(meta) = &attr.meta {
+            if let syn::Expr::Lit(syn::ExprLit {
+                lit: syn::Lit::Str(lit),
+                ..
+            }) = &meta.value
+            {
+                let line = lit.value();
+                let line = line.trim();
+                if !line.is_empty() {
+                    return line.to_owned();
+                }
+            }
+        }
+    }
+    String::new()
+}
+
+fn type_name(ty: &Type) -> String {
+    quote!(#ty).to_string().replace(' ', "")
+}
+
+/// If `ty` is `Wrapper<T>` for the given wrapper name, returns `T`.
+fn wrapped_type<'a>(ty: &'a Type, wrapper: &str) -> Option<&'a Type> {
+    let segment = match ty {
+        Type::Path(path) => path.path.segments.last()?,
+        _ => return None,
+    };
+    let args = match &segment.arguments {
+        syn::PathArguments::AngleBracketed(args) if segment.ident == wrapper => args,
+        _ => return None,
+    };
+    match args.args.first()? {
+        syn::GenericArgument::Type(inner) if args.args.len() == 1 => Some(inner),
+        _ => None,
+    }
+}
+
+fn is_bool(ty: &Type) -> bool {
+    matches!(ty, Type::Path(path) if path.path.is_ident("bool"))
+}
+
+/// An argument of a command, from a single field of a variant.
+struct Arg<'a> {
+    name: String,
+    kind: ArgKind,
+    ty: &'a Type,
+    optional: bool,
+    short: Option<LitChar>,
+    default: Option<LitStr>,
+}
+
+#[derive(Clone, Copy)]
+enum ArgKind {
+    Positional,
+    Optional,
+    Named,
+    Flag,
+    Rest,
+}
+
+impl<'a> Arg<'a> {
+    fn parse(field: &'a Field, name: String) -> syn::Result<Self> {
+        let mut named = false;
+        let mut short = None;
+        let mut default = None;
+        for attr in &field.attrs {
+            if attr.path().is_ident("command") {
+                attr.parse_nested_meta(|meta| {
+                    if meta.path.is_ident("named") {
+                        named = true;
+                    } else if meta.path.is_ident("short") {
+                        short = Some(meta.value()?.parse()?);
+                    } else if meta.path.is_ident("default") {
+                        default = Some(meta.value()?.parse()?);
+                    } else {
+                        return Err(meta.error("unsupported command attribute"));
+                    }
+                    Ok(())
+                })?;
+            }
+        }
+
+        let option = wrapped_type(&field.ty, "Option");
+        let vec = wrapped_type(&field.ty, "Vec");
+        let (kind, ty) = if named || short.is_some() {
+            if is_bool(&field.ty) {
+                (ArgKind::Flag, &field.ty)
+            } else {
+                (ArgKind::Named, option.unwrap_or(&field.ty))
+            }
+        } else if let Some(inner) = option {
+            (ArgKind::Optional, inner)
+        } else if let Some(inner) = vec {
+            (ArgKind::Rest, inner)
+        } else if default.is_some() {
+            (ArgKind::Optional, &field.ty)
+        } else {
+            (ArgKind::Positional, &field.ty)
+        };
+
+        Ok(Arg {
+            name,
+            kind,
+            ty,
+            optional: option.is_some(),
+            short,
+            default,
+        })
+    }
+
+    /// The call that adds this argument to a `dbgcmd::Signature`.
+    fn signature(&self) -> TokenStream2 {
+        let Arg { name, ty, .. } = self;
+        let mut call = match self.kind {
+            ArgKind::Positional => quote!(.positional::<#ty>(#name)),
+            ArgKind::Optional => quote!(.optional::<#ty>(#name)),
+            ArgKind::Named => quote!(.named::<#ty>(#name)),
+            ArgKind::Flag => quote!(.flag(#name)),
+            ArgKind::Rest => quote!(.rest::<#ty>(#name)),
+        };
+        if let Some(short) = &self.short {
+            call.extend(quote!(.short(#short)));
+        }
+        if let Some(default) = &self.default {
+            call.extend(quote!(.default_value(#default)));
+        }
+        call
+    }
+
+    /// The expression that reads this argument from a `dbgcmd::Args`.
+    fn value(&self) -> TokenStream2 {
+        let Arg { name, ty, .. } = self;
+        match self.kind {
+            ArgKind::Flag => quote!(args.flag(#name)),
+            ArgKind::Rest => quote!(args.values::<#ty>(#name)?),
+            _ if self.optional => quote!(args.opt_value::<#ty>(#name)?),
+            _ => quote!(args.value::<#ty>(#name)?),
+        }
+    }
+
+    fn info(&self) -> TokenStream2 {
+        let name = &self.name;
+        let ty = type_name(self.ty);
+        let kind = format_ident!(
+            "{}",
+            match self.kind {
+                ArgKind::Positional => "Positional",
+                ArgKind::Optional => "Optional",
+                ArgKind::Named => "Named",
+                ArgKind::Flag => "Flag",
+                ArgKind::Rest => "Rest",
+            }
+        );
+        quote! {
+            ::dbgcmd::ArgInfo {
+                name: #name,
+                ty: #ty,
+                kind: ::dbgcmd::ArgKind::#kind,
+            }
+        }
+    }
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let ident = &input.ident;
+    let data = match &input.data {
+        Data::Enum(data) => data,
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "DebugCommand can only be derived for enums",
+            ))
+        }
+    };
+
+    let mut rename_rule = RenameRule::Kebab;
+    for attr in &input.attrs {
+        if attr.path().is_ident("command") {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename_all") {
+                    rename_rule = RenameRule::parse(&meta.value()?.parse()?)?;
+                    Ok(())
+                } else {
+                    Err(meta.error("unsupported command attribute"))
+                }
+            })?;
+        }
+    }
+
+    let mut parse_arms = Vec::new();
+    let mut infos = Vec::new();
+
+    for variant in &data.variants {
+        let variant_ident = &variant.ident;
+
+        let mut name = rename_rule.apply(&variant_ident.to_string());
+        for attr in &variant.attrs {
+            if attr.path().is_ident("command") {
+                attr.parse_nested_meta(|meta| {
+                    if meta.path.is_ident("name") {
+                        name = meta.value()?.parse::<LitStr>()?.value();
+                        Ok(())
+                    } else {
+                        Err(meta.error("unsupported command attribute"))
+                    }
+                })?;
+            }
+        }
+
+        let args = match &variant.fields {
+            Fields::Named(fields) => fields
+                .named
+                .iter()
+                .map(|field| Arg::parse(field, field.ident.as_ref().unwrap().to_string()))
+                .collect::<syn::Result<Vec<_>>>()?,
+            Fields::Unnamed(fields) => fields
+                .unnamed
+                .iter()
+                .enumerate()
+                .map(|(i, field)| Arg::parse(field, format!("arg{}", i + 1)))
+                .collect::<syn::Result<Vec<_>>>()?,
+            Fields::Unit => Vec::new(),
+        };
+
+        let signature = args.iter().map(Arg::signature);
+        let values = args.iter().map(Arg::value);
+        let construct = match &variant.fields {
+            Fields::Named(fields) => {
+                let field_idents = fields.named.iter().map(|field| &field.ident);
+                quote!(#ident::#variant_ident { #( #field_idents: #values ),* })
+            }
+            Fields::Unnamed(_) => quote!(#ident::#variant_ident( #( #values ),* )),
+            Fields::Unit => quote!(#ident::#variant_ident),
+        };
+
+        parse_arms.push(quote! {
+            #name => {
+                let signature = ::dbgcmd::Signature::new() #( #signature )*;
+                let args = signature.parse(rest)?;
+                ::std::result::Result::Ok(#construct)
+            }
+        });
+
+        let help = doc_summary(&variant.attrs);
+        let arg_infos = args.iter().map(Arg::info);
+        infos.push(quote! {
+            ::dbgcmd::CommandInfo {
+                name: #name,
+                args: &[ #( #arg_infos ),* ],
+                help: #help,
+            }
+        });
+    }
+
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::std::str::FromStr for #ident #ty_generics #where_clause {
+            type Err = ::dbgcmd::Error;
+
+            fn from_str(s: &str) -> ::std::result::Result<Self, Self::Err> {
+                let tokens = ::dbgcmd::tokenize(s)?;
+                let (name, rest) = match tokens.split_first() {
+                    ::std::option::Option::Some((name, rest)) => (name, rest),
+                    ::std::option::Option::None => {
+                        return ::std::result::Result::Err(::dbgcmd::Error::new(
+                            ::dbgcmd::ErrorKind::Empty,
+                            ::std::option::Option::None,
+                        ));
+                    }
+                };
+                match name.as_str() {
+                    #( #parse_arms )*
+                    _ => ::std::result::Result::Err(::dbgcmd::Error::new(
+                        ::dbgcmd::ErrorKind::UnknownCommand {
+                            name: ::std::clone::Clone::clone(&name.text),
+                        },
+                        ::std::option::Option::Some(::std::clone::Clone::clone(&name.span)),
+                    )),
+                }
+            }
+        }
+
+        impl #impl_generics ::dbgcmd::DebugCommand for #ident #ty_generics #where_clause {
+            fn commands() -> &'static [::dbgcmd::CommandInfo] {
+                &[ #( #infos ),* ]
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renaming() {
+        assert_eq!(RenameRule::Kebab.apply("SayHi"), "say-hi");
+        assert_eq!(RenameRule::Snake.apply("HTTPServerId"), "http_server_id");
+        assert_eq!(RenameRule::Lower.apply("Noclip2D"), "noclip2d");
+        assert_eq!(RenameRule::Kebab.apply("Give_Gold"), "give-gold");
+    }
+}
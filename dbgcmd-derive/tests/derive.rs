@@ -0,0 +1,160 @@
+use dbgcmd::{ArgInfo, ArgKind, CommandInfo, Console, DebugCommand};
+
+#[derive(Debug, PartialEq, dbgcmd_derive::DebugCommand)]
+enum Command {
+    /// Say hello.
+    SayHi,
+
+    /// Teleport the player.
+    ///
+    /// This line is not part of the help.
+    #[command(name = "tp")]
+    Teleport {
+        x: f32,
+        y: f32,
+    },
+
+    SpawnEnemy(String, u32),
+
+    /// Spawn a wave of enemies.
+    Wave {
+        names: Vec<String>,
+        #[command(short = 'n', default = "1")]
+        count: u32,
+        #[command(named)]
+        speed: Option<f32>,
+        #[command(short = 'q')]
+        quiet: bool,
+    },
+
+    Give(String, Option<u32>),
+}
+
+#[derive(Debug, PartialEq, dbgcmd_derive::DebugCommand)]
+#[command(rename_all = "snake_case")]
+enum Snake {
+    NoClip,
+    GodMode(bool),
+}
+
+#[test]
+fn parses_variants() {
+    assert_eq!("say-hi".parse(), Ok(Command::SayHi));
+    assert_eq!(
+        "tp 1 -2.5".parse(),
+        Ok(Command::Teleport { x: 1.0, y: -2.5 })
+    );
+    assert_eq!(
+        "spawn-enemy 'big orc' 3".parse(),
+        Ok(Command::SpawnEnemy("big orc".into(), 3))
+    );
+    assert_eq!("no_clip".parse(), Ok(Snake::NoClip));
+    assert_eq!("god_mode true".parse(), Ok(Snake::GodMode(true)));
+}
+
+#[test]
+fn parses_options() {
+    assert_eq!(
+        "wave orc imp -q --speed=2.5".parse(),
+        Ok(Command::Wave {
+            names: vec!["orc".into(), "imp".into()],
+            count: 1,
+            speed: Some(2.5),
+            quiet: true,
+        })
+    );
+    assert_eq!(
+        "wave -n 3".parse(),
+        Ok(Command::Wave {
+            names: vec![],
+            count: 3,
+            speed: None,
+            quiet: false,
+        })
+    );
+    assert_eq!(
+        "give gold 5".parse(),
+        Ok(Command::Give("gold".into(), Some(5)))
+    );
+    assert_eq!("give gold".parse(), Ok(Command::Give("gold".into(), None)));
+}
+
+fn error(entry: &str) -> String {
+    entry.parse::<Command>().unwrap_err().to_string()
+}
+
+#[test]
+fn reports_errors() {
+    assert_eq!(error("fly"), "unknown command \"fly\"");
+    assert_eq!(error(""), "no command entered");
+    assert_eq!(error("say-hi there"), "unexpected argument \"there\"");
+    assert_eq!(error("tp 1"), "missing argument y");
+    assert_eq!(
+        error("spawn-enemy orc many"),
+        "invalid value \"many\" for argument arg2 (expected u32)"
+    );
+    assert_eq!(
+        error("wave orc --speed fast"),
+        "invalid value \"fast\" for argument speed (expected f32)"
+    );
+    assert_eq!(error("tp 'one"), "unterminated ' quote");
+}
+
+#[test]
+fn renders_errors() {
+    let mut console = Console::new();
+    console.set_entry("tp 1 north".into());
+
+    let command = console.confirm_rendered::<Command>();
+    if console.enabled() {
+        assert_eq!(
+            command,
+            Err(
+                "tp 1 north\n     ^^^^^ invalid value \"north\" for argument y (expected f32)"
+                    .into()
+            )
+        );
+    }
+}
+
+#[test]
+fn metadata() {
+    assert_eq!(
+        Command::commands()[1],
+        CommandInfo {
+            name: "tp",
+            args: &[
+                ArgInfo {
+                    name: "x",
+                    ty: "f32",
+                    kind: ArgKind::Positional,
+                },
+                ArgInfo {
+                    name: "y",
+                    ty: "f32",
+                    kind: ArgKind::Positional,
+                },
+            ],
+            help: "Teleport the player.",
+        }
+    );
+    assert_eq!(
+        Command::help(),
+        "say-hi - Say hello.\n\
+         tp <x> <y> - Teleport the player.\n\
+         spawn-enemy <arg1> <arg2>\n\
+         wave [names...] [--count <u32>] [--speed <f32>] [--quiet] - Spawn a wave of enemies.\n\
+         give <arg1> [arg2]"
+    );
+}
+
+#[test]
+fn works_with_console() {
+    let mut console = Console::new();
+    console.set_entry("spawn-enemy goblin 2".into());
+
+    let command = console.confirm::<Command>();
+    if console.enabled() {
+        assert_eq!(command, Ok(Command::SpawnEnemy("goblin".into(), 2)));
+    }
+}
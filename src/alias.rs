@@ -0,0 +1,142 @@
+//! Expanding aliases at the start of an entry into full commands.
+
+use std::collections::BTreeMap;
+
+use crate::error::{Error, ErrorKind};
+use crate::tokenize::{tokenize, Token};
+
+/// Replaces `$1` to `$9` in `expansion` with the arguments of the same number,
+/// `$*` with all of the arguments, and `$$` with `$`. Arguments are copied from
+/// `entry` as they were typed, quotes and all.
+///
+/// If `expansion` doesn't use any arguments, they are all appended to it
+/// instead.
+fn substitute(expansion: &str, entry: &str, args: &[Token]) -> String {
+    let all = match args.first() {
+        Some(first) => &entry[first.span.start..],
+        None => "",
+    };
+    let mut expanded = String::with_capacity(expansion.len() + all.len());
+    let mut used_args = false;
+    let mut chars = expansion.chars().peekable();
+    while let Some(ch) = chars.next() {
+        if ch != '$' {
+            expanded.push(ch);
+            continue;
+        }
+        match chars.peek() {
+            Some('*') => {
+                expanded.push_str(all.trim_end());
+                used_args = true;
+            }
+            Some(&digit @ '1'..='9') => {
+                let index = digit as usize - '1' as usize;
+                if let Some(arg) = args.get(index) {
+                    expanded.push_str(&entry[arg.span.clone()]);
+                }
+                used_args = true;
+            }
+            Some('$') => expanded.push('$'),
+            _ => {
+                expanded.push('$');
+                continue;
+            }
+        }
+        chars.next();
+    }
+    if !used_args && !all.is_empty() {
+        expanded.push(' ');
+        expanded.push_str(all);
+    }
+    expanded
+}
+
+/// Expands the alias named by the first token of `entry`, and then any alias
+/// that the expansion starts with, until it starts with something that isn't
+/// an alias.
+///
+/// Like in a shell, an alias whose expansion starts with its own name isn't
+/// expanded again, so `spawn` can wrap the `spawn` command. Aliases that lead
+/// back to an earlier alias, like `a -> b -> a`, are an error.
+///
+/// Entries that can't be tokenized are returned as they are, so that the error
+/// is reported when they are parsed.
+pub(crate) fn expand(aliases: &BTreeMap<String, String>, entry: &str) -> Result<String, Error> {
+    let mut entry = entry.to_owned();
+    let mut expanded: Vec<String> = Vec::new();
+    let mut span = None;
+    loop {
+        let tokens = match tokenize(&entry) {
+            Ok(tokens) => tokens,
+            Err(_) => return Ok(entry),
+        };
+        let (name, args) = match tokens.split_first() {
+            Some(split) => split,
+            None => return Ok(entry),
+        };
+        let expansion = match aliases.get(&name.text) {
+            Some(expansion) => expansion,
+            None => return Ok(entry),
+        };
+        if expanded.last() == Some(&name.text) {
+            return Ok(entry);
+        }
+        span.get_or_insert(name.span.clone());
+        let cycle = expanded.contains(&name.text);
+        expanded.push(name.text.clone());
+        if cycle {
+            return Err(Error::new(ErrorKind::AliasCycle { names: expanded }, span));
+        }
+        entry = substitute(expansion, &entry, args);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn aliases(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs
+            .iter()
+            .map(|(name, expansion)| (name.to_string(), expansion.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn substitution() {
+        let aliases = aliases(&[
+            ("g", "give gold"),
+            ("tp", "teleport --x $1 --y $2"),
+            ("say", "chat all $*"),
+            ("cost", "echo $$$1 $3 $x"),
+            ("gg", "g"),
+            ("kill", "kill --safe"),
+            ("ls", "ls"),
+            ("k", "kill"),
+        ]);
+        let expand = |entry| expand(&aliases, entry).unwrap();
+
+        assert_eq!(expand("g 100"), "give gold 100");
+        assert_eq!(expand("g"), "give gold");
+        assert_eq!(expand("gg 5"), "give gold 5");
+        assert_eq!(expand("tp 1 -2 3"), "teleport --x 1 --y -2");
+        assert_eq!(expand("say  'hi there'  all "), "chat all 'hi there'  all");
+        assert_eq!(expand("cost 5"), "echo $5  $x");
+        assert_eq!(expand("spawn g"), "spawn g");
+        assert_eq!(expand("g 'unterminated"), "g 'unterminated");
+        assert_eq!(expand("kill orc"), "kill --safe orc");
+        assert_eq!(expand("k orc"), "kill --safe orc");
+        assert_eq!(expand("ls -a"), "ls -a");
+    }
+
+    #[test]
+    fn cycles() {
+        let aliases = aliases(&[("a", "b 1"), ("b", "c"), ("c", "a"), ("x", "$1")]);
+
+        let error = expand(&aliases, " a").unwrap_err();
+        assert_eq!(error.render(" a"), " a\n ^ alias cycle: a -> b -> c -> a");
+        assert_eq!(expand(&aliases, "x spawn").unwrap(), "spawn");
+        assert_eq!(expand(&aliases, "x x").unwrap(), "x");
+        assert!(expand(&aliases, "x a").is_err());
+    }
+}
@@ -0,0 +1,608 @@
+//! Arguments passed to command handlers, and typed parsing of them.
+
+use std::fmt;
+use std::ops::Range;
+use std::str::FromStr;
+
+use crate::tokenize::Token;
+
+/// The arguments given to a registered command, not including the command
+/// name itself.
+///
+/// The raw tokens can always be accessed by position. If the command was
+/// registered with a `Signature`, the arguments have also been matched up to
+/// its parameters and checked, and can be accessed by name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Args<'a> {
+    tokens: &'a [Token],
+    values: Vec<Value<'a>>,
+    flags: Vec<&'a str>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Value<'a> {
+    name: &'a str,
+    text: &'a str,
+    span: Option<Range<usize>>,
+}
+
+impl<'a> Args<'a> {
+    pub fn new(tokens: &'a [Token]) -> Self {
+        Args {
+            tokens,
+            values: Vec::new(),
+            flags: Vec::new(),
+        }
+    }
+
+    /// The number of arguments.
+    pub fn len(&self) -> usize {
+        self.tokens.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tokens.is_empty()
+    }
+
+    /// Returns the text of the argument at `index`, if there is one.
+    pub fn get(&self, index: usize) -> Option<&'a str> {
+        self.tokens.get(index).map(Token::as_str)
+    }
+
+    /// Returns an iterator over the text of each argument.
+    pub fn iter(&self) -> impl Iterator<Item = &'a str> {
+        self.tokens.iter().map(Token::as_str)
+    }
+
+    /// Returns the tokens of the arguments, including their spans in the
+    /// original entry.
+    pub fn tokens(&self) -> &'a [Token] {
+        self.tokens
+    }
+
+    /// Parses the argument at `index` using `FromStr`.
+    pub fn parse<T: FromStr>(&self, index: usize) -> Result<T, ArgError> {
+        let name = (index + 1).to_string();
+        let token = self
+            .tokens
+            .get(index)
+            .ok_or_else(|| ArgError::Missing { name: name.clone() })?;
+        parse_value(&name, &token.text, Some(token.span.clone()))
+    }
+
+    /// Parses the value of the named parameter, or its default value if it was
+    /// not given.
+    ///
+    /// Returns `ArgError::Missing` if the parameter has no value.
+    pub fn value<T: FromStr>(&self, name: &str) -> Result<T, ArgError> {
+        self.opt_value(name)?.ok_or_else(|| ArgError::Missing {
+            name: name.to_owned(),
+        })
+    }
+
+    /// Parses the value of the named parameter, or its default value if it was
+    /// not given. Returns `None` if the parameter has no value.
+    ///
+    /// If an option was given more than once, the last value is used.
+    pub fn opt_value<T: FromStr>(&self, name: &str) -> Result<Option<T>, ArgError> {
+        self.values
+            .iter()
+            .rev()
+            .find(|value| value.name == name)
+            .map(|value| parse_value(name, value.text, value.span.clone()))
+            .transpose()
+    }
+
+    /// Parses every value given for the named parameter. This is mainly for
+    /// the variadic parameter added with `Signature::rest`.
+    pub fn values<T: FromStr>(&self, name: &str) -> Result<Vec<T>, ArgError> {
+        self.values
+            .iter()
+            .filter(|value| value.name == name)
+            .map(|value| parse_value(name, value.text, value.span.clone()))
+            .collect()
+    }
+
+    /// Whether the named flag was given.
+    pub fn flag(&self, name: &str) -> bool {
+        self.flags.contains(&name)
+    }
+}
+
+fn parse_value<T: FromStr>(
+    name: &str,
+    text: &str,
+    span: Option<Range<usize>>,
+) -> Result<T, ArgError> {
+    text.parse().map_err(|_| ArgError::Invalid {
+        name: name.to_owned(),
+        value: text.to_owned(),
+        expected: short_type_name::<T>(),
+        span,
+    })
+}
+
+/// Returns the name of a type without any module paths, so
+/// `alloc::vec::Vec<alloc::string::String>` becomes `Vec<String>`.
+fn short_type_name<T>() -> String {
+    let full = std::any::type_name::<T>();
+    let mut short = String::with_capacity(full.len());
+    let mut word = String::new();
+    let mut chars = full.chars().peekable();
+    while let Some(ch) = chars.next() {
+        if ch == ':' && chars.peek() == Some(&':') {
+            chars.next();
+            word.clear();
+        } else if ch.is_alphanumeric() || ch == '_' {
+            word.push(ch);
+        } else {
+            short.push_str(&word);
+            short.push(ch);
+            word.clear();
+        }
+    }
+    short.push_str(&word);
+    short
+}
+
+fn accepts<T: FromStr>(text: &str) -> bool {
+    text.parse::<T>().is_ok()
+}
+
+/// An error from matching or parsing the arguments of a command.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ArgError {
+    /// A required argument was not given.
+    Missing { name: String },
+
+    /// An argument could not be parsed as the expected type.
+    Invalid {
+        name: String,
+        value: String,
+        expected: String,
+        span: Option<Range<usize>>,
+    },
+
+    /// More positional arguments were given than the command takes.
+    Unexpected { value: String, span: Range<usize> },
+
+    /// An option was given that the command does not have.
+    UnknownOption { option: String, span: Range<usize> },
+
+    /// An option that takes a value was given without one.
+    MissingValue { option: String, span: Range<usize> },
+}
+
+impl ArgError {
+    /// The byte range of the entry that the error refers to, if any.
+    pub fn span(&self) -> Option<Range<usize>> {
+        match self {
+            ArgError::Missing { .. } => None,
+            ArgError::Invalid { span, .. } => span.clone(),
+            ArgError::Unexpected { span, .. }
+            | ArgError::UnknownOption { span, .. }
+            | ArgError::MissingValue { span, .. } => Some(span.clone()),
+        }
+    }
+}
+
+impl fmt::Display for ArgError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ArgError::Missing { name } => write!(f, "missing argument {}", name),
+            ArgError::Invalid {
+                name,
+                value,
+                expected,
+                ..
+            } => write!(
+                f,
+                "invalid value {:?} for argument {} (expected {})",
+                value, name, expected
+            ),
+            ArgError::Unexpected { value, .. } => write!(f, "unexpected argument {:?}", value),
+            ArgError::UnknownOption { option, .. } => write!(f, "unknown option {}", option),
+            ArgError::MissingValue { option, .. } => {
+                write!(f, "missing value for option {}", option)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ArgError {}
+
+impl From<ArgError> for String {
+    fn from(error: ArgError) -> String {
+        error.to_string()
+    }
+}
+
+/// How a parameter of a `Signature` is given.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ArgKind {
+    /// A required positional argument.
+    Positional,
+
+    /// A positional argument that may be left out.
+    Optional,
+
+    /// An option given as `--name value` or `--name=value`.
+    Named,
+
+    /// An option given as `--name` with no value.
+    Flag,
+
+    /// Any number of positional arguments after all the others.
+    Rest,
+}
+
+impl ArgKind {
+    /// Formats an argument of this kind for a usage line.
+    pub(crate) fn usage(self, name: &str, ty: &str) -> String {
+        match self {
+            ArgKind::Positional => format!("<{}>", name),
+            ArgKind::Optional => format!("[{}]", name),
+            ArgKind::Named => format!("[--{} <{}>]", name, ty),
+            ArgKind::Flag => format!("[--{}]", name),
+            ArgKind::Rest => format!("[{}...]", name),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Param {
+    name: String,
+    kind: ArgKind,
+    short: Option<char>,
+    ty: String,
+    default: Option<String>,
+    accepts: fn(&str) -> bool,
+}
+
+/// Describes the arguments a command takes, so that they can be matched up
+/// and checked before the command's handler runs.
+///
+/// Each parameter has a type, and the argument given for it must parse as
+/// that type using `FromStr`. Errors name the parameter and the type that was
+/// expected.
+///
+/// # Examples
+///
+/// ```rust
+/// use dbgcmd::{tokenize, Signature};
+///
+/// let signature = Signature::new()
+///     .positional::<String>("enemy")
+///     .optional::<u32>("count")
+///     .default_value("1")
+///     .named::<f32>("speed")
+///     .short('s')
+///     .flag("verbose")
+///     .short('v');
+///
+/// let tokens = tokenize("orc -v --speed 3.5").unwrap();
+/// let args = signature.parse(&tokens).unwrap();
+///
+/// assert_eq!(args.value::<String>("enemy").unwrap(), "orc");
+/// assert_eq!(args.value::<u32>("count").unwrap(), 1);
+/// assert_eq!(args.value::<f32>("speed").unwrap(), 3.5);
+/// assert!(args.flag("verbose"));
+///
+/// assert_eq!(
+///     signature.usage(),
+///     "<enemy> [count] [--speed <f32>] [--verbose]"
+/// );
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Signature {
+    params: Vec<Param>,
+}
+
+impl Signature {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn param<T: FromStr>(mut self, name: &str, kind: ArgKind) -> Self {
+        self.params.push(Param {
+            name: name.to_owned(),
+            kind,
+            short: None,
+            ty: short_type_name::<T>(),
+            default: None,
+            accepts: accepts::<T>,
+        });
+        self
+    }
+
+    /// Adds a required positional parameter.
+    pub fn positional<T: FromStr>(self, name: &str) -> Self {
+        self.param::<T>(name, ArgKind::Positional)
+    }
+
+    /// Adds a positional parameter that may be left out. Optional parameters
+    /// are filled in order after the required ones.
+    pub fn optional<T: FromStr>(self, name: &str) -> Self {
+        self.param::<T>(name, ArgKind::Optional)
+    }
+
+    /// Adds an option given as `--name value` or `--name=value`.
+    pub fn named<T: FromStr>(self, name: &str) -> Self {
+        self.param::<T>(name, ArgKind::Named)
+    }
+
+    /// Adds an option given as `--name` with no value.
+    pub fn flag(self, name: &str) -> Self {
+        self.param::<bool>(name, ArgKind::Flag)
+    }
+
+    /// Adds a parameter that collects every positional argument left over
+    /// after the others are filled.
+    pub fn rest<T: FromStr>(self, name: &str) -> Self {
+        self.param::<T>(name, ArgKind::Rest)
+    }
+
+    /// Lets the most recently added option or flag also be given as `-c`.
+    pub fn short(mut self, short: char) -> Self {
+        if let Some(param) = self.params.last_mut() {
+            param.short = Some(short);
+        }
+        self
+    }
+
+    /// Sets a default value for the most recently added parameter, used when
+    /// it is not given. The default is checked against the parameter's type
+    /// in the same way as a given value.
+    pub fn default_value(mut self, value: &str) -> Self {
+        if let Some(param) = self.params.last_mut() {
+            param.default = Some(value.to_owned());
+        }
+        self
+    }
+
+    /// Returns a usage string for the parameters, like
+    /// `<x> [y] [--speed <f32>] [names...]`.
+    pub fn usage(&self) -> String {
+        let parts: Vec<String> = self
+            .params
+            .iter()
+            .map(|param| param.kind.usage(&param.name, &param.ty))
+            .collect();
+        parts.join(" ")
+    }
+
+    fn find_option(&self, long: Option<&str>, short: Option<char>) -> Option<&Param> {
+        self.params.iter().find(|param| {
+            matches!(param.kind, ArgKind::Named | ArgKind::Flag)
+                && (long.is_some_and(|long| long == param.name)
+                    || short.is_some() && short == param.short)
+        })
+    }
+
+    /// Matches the tokens up to the parameters, and checks that each value
+    /// parses as the type of its parameter.
+    ///
+    /// Arguments starting with `--`, or a `-` followed by a single letter, are
+    /// options. Anything after a lone `--` is positional.
+    pub fn parse<'a>(&'a self, tokens: &'a [Token]) -> Result<Args<'a>, ArgError> {
+        let mut args = Args::new(tokens);
+        let mut positionals = self
+            .params
+            .iter()
+            .filter(|param| matches!(param.kind, ArgKind::Positional | ArgKind::Optional));
+        let rest = self.params.iter().find(|param| param.kind == ArgKind::Rest);
+        let mut options_ended = false;
+        let mut tokens = tokens.iter();
+
+        while let Some(token) = tokens.next() {
+            let text = token.as_str();
+            let option = match text.strip_prefix("--") {
+                _ if options_ended => None,
+                Some("") => {
+                    options_ended = true;
+                    continue;
+                }
+                Some(long) => Some(
+                    long.split_once('=')
+                        .map_or((long, None), |(name, value)| (name, Some(value))),
+                ),
+                None => None,
+            };
+            let short = match text.strip_prefix('-') {
+                _ if options_ended || option.is_some() => None,
+                Some(short) if short.chars().count() == 1 => {
+                    short.chars().next().filter(|ch| ch.is_alphabetic())
+                }
+                _ => None,
+            };
+
+            if option.is_some() || short.is_some() {
+                let long = option.map(|(long, _)| long);
+                let param =
+                    self.find_option(long, short)
+                        .ok_or_else(|| ArgError::UnknownOption {
+                            option: text.to_owned(),
+                            span: token.span.clone(),
+                        })?;
+                if param.kind == ArgKind::Flag {
+                    args.flags.push(&param.name);
+                    continue;
+                }
+                let value = match option.and_then(|(_, value)| value) {
+                    Some(value) => Value {
+                        name: &param.name,
+                        text: value,
+                        span: Some(token.span.clone()),
+                    },
+                    None => {
+                        let value = tokens.next().ok_or_else(|| ArgError::MissingValue {
+                            option: text.to_owned(),
+                            span: token.span.clone(),
+                        })?;
+                        Value {
+                            name: &param.name,
+                            text: value.as_str(),
+                            span: Some(value.span.clone()),
+                        }
+                    }
+                };
+                check(param, &value)?;
+                args.values.push(value);
+                continue;
+            }
+
+            let param = positionals
+                .next()
+                .or(rest)
+                .ok_or_else(|| ArgError::Unexpected {
+                    value: text.to_owned(),
+                    span: token.span.clone(),
+                })?;
+            let value = Value {
+                name: &param.name,
+                text,
+                span: Some(token.span.clone()),
+            };
+            check(param, &value)?;
+            args.values.push(value);
+        }
+
+        for param in &self.params {
+            if args.values.iter().any(|value| value.name == param.name) {
+                continue;
+            }
+            match &param.default {
+                Some(default) => {
+                    let value = Value {
+                        name: &param.name,
+                        text: default,
+                        span: None,
+                    };
+                    check(param, &value)?;
+                    args.values.push(value);
+                }
+                None if param.kind == ArgKind::Positional => {
+                    return Err(ArgError::Missing {
+                        name: param.name.clone(),
+                    });
+                }
+                None => (),
+            }
+        }
+
+        Ok(args)
+    }
+}
+
+fn check(param: &Param, value: &Value) -> Result<(), ArgError> {
+    if (param.accepts)(value.text) {
+        Ok(())
+    } else {
+        Err(ArgError::Invalid {
+            name: param.name.clone(),
+            value: value.text.to_owned(),
+            expected: param.ty.clone(),
+            span: value.span.clone(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokenize;
+
+    fn signature() -> Signature {
+        Signature::new()
+            .positional::<f32>("x")
+            .positional::<f32>("y")
+            .optional::<String>("label")
+            .named::<f32>("speed")
+            .short('s')
+            .default_value("1.0")
+            .flag("quiet")
+            .short('q')
+            .rest::<u8>("tags")
+    }
+
+    #[test]
+    fn matches_parameters() {
+        let signature = signature();
+        let tokens = tokenize("-q 1 -2.5 --speed=3 home 7 8").unwrap();
+        let args = signature.parse(&tokens).unwrap();
+
+        assert_eq!(args.value::<f32>("x"), Ok(1.0));
+        assert_eq!(args.value::<f32>("y"), Ok(-2.5));
+        assert_eq!(args.opt_value::<String>("label"), Ok(Some("home".into())));
+        assert_eq!(args.value::<f32>("speed"), Ok(3.0));
+        assert_eq!(args.values::<u8>("tags"), Ok(vec![7, 8]));
+        assert!(args.flag("quiet"));
+        assert_eq!(args.get(0), Some("-q"));
+
+        let tokens = tokenize("1 2").unwrap();
+        let args = signature.parse(&tokens).unwrap();
+        assert_eq!(args.opt_value::<String>("label"), Ok(None));
+        assert_eq!(args.value::<f32>("speed"), Ok(1.0));
+        assert_eq!(args.values::<u8>("tags"), Ok(vec![]));
+        assert!(!args.flag("quiet"));
+
+        let tokens = tokenize("1 2 --speed 4 -s 5 --speed=6").unwrap();
+        let args = signature.parse(&tokens).unwrap();
+        assert_eq!(args.value::<f32>("speed"), Ok(6.0));
+        assert_eq!(
+            args.value::<String>("label"),
+            Err(ArgError::Missing {
+                name: "label".into()
+            })
+        );
+    }
+
+    #[test]
+    fn reports_errors() {
+        let signature = signature();
+        let parse = |entry| {
+            let tokens = tokenize(entry).unwrap();
+            signature
+                .parse(&tokens)
+                .map(|_| ())
+                .unwrap_err()
+                .to_string()
+        };
+
+        assert_eq!(parse("1"), "missing argument y");
+        assert_eq!(
+            parse("1 two"),
+            "invalid value \"two\" for argument y (expected f32)"
+        );
+        assert_eq!(
+            parse("1 2 -s fast"),
+            "invalid value \"fast\" for argument speed (expected f32)"
+        );
+        assert_eq!(parse("1 2 --loud"), "unknown option --loud");
+        assert_eq!(parse("1 2 --speed"), "missing value for option --speed");
+        assert_eq!(
+            parse("1 2 l 300"),
+            "invalid value \"300\" for argument tags (expected u8)"
+        );
+
+        let strict = Signature::new().positional::<String>("name");
+        let tokens = tokenize("a -- -b").unwrap();
+        assert_eq!(
+            strict.parse(&tokens),
+            Err(ArgError::Unexpected {
+                value: "-b".into(),
+                span: 5..7
+            })
+        );
+    }
+
+    #[test]
+    fn type_names() {
+        assert_eq!(short_type_name::<String>(), "String");
+        assert_eq!(
+            short_type_name::<Vec<Option<String>>>(),
+            "Vec<Option<String>>"
+        );
+        assert_eq!(short_type_name::<(u8, f32)>(), "(u8, f32)");
+    }
+}
@@ -0,0 +1,54 @@
+//! Clipboard access for copying, cutting, and pasting text in the entry.
+//!
+//! This crate never touches the system clipboard itself. Instead, implement
+//! `Clipboard` for whatever clipboard your application has access to, and
+//! pass it to `Console::copy`, `Console::cut`, or `Console::paste`.
+
+/// A clipboard which text can be copied to and pasted from.
+pub trait Clipboard {
+    /// Returns the text currently on the clipboard, if there is any.
+    fn get(&mut self) -> Option<String>;
+
+    /// Replaces the contents of the clipboard with `text`.
+    fn set(&mut self, text: String);
+}
+
+/// A clipboard that only exists in memory. Useful for tests, or when your
+/// application has no access to the system clipboard.
+///
+/// # Examples
+///
+/// ```rust
+/// use dbgcmd::{Clipboard, MemoryClipboard};
+///
+/// let mut clipboard = MemoryClipboard::new();
+/// assert_eq!(clipboard.get(), None);
+///
+/// clipboard.set("copied".to_owned());
+/// assert_eq!(clipboard.get(), Some("copied".to_owned()));
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct MemoryClipboard {
+    contents: Option<String>,
+}
+
+impl MemoryClipboard {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the text currently on the clipboard without copying it.
+    pub fn contents(&self) -> Option<&str> {
+        self.contents.as_deref()
+    }
+}
+
+impl Clipboard for MemoryClipboard {
+    fn get(&mut self) -> Option<String> {
+        self.contents.clone()
+    }
+
+    fn set(&mut self, text: String) {
+        self.contents = Some(text);
+    }
+}
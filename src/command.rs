@@ -0,0 +1,119 @@
+//! Metadata describing the commands of a command type, for help text and
+//! completion.
+
+use std::str::FromStr;
+
+use crate::args::ArgKind;
+
+/// A command type whose commands can be listed, for help text and completion.
+///
+/// With the `derive` feature, this can be derived for an enum along with
+/// `FromStr`. Each variant becomes a command, named after the variant in
+/// kebab-case, and each field becomes an argument parsed with `FromStr`.
+///
+/// - `#[command(rename_all = "...")]` on the enum changes how every variant is
+///   named. It can be `"kebab-case"`, `"snake_case"`, or `"lowercase"`.
+/// - `#[command(name = "...")]` on a variant sets its name explicitly.
+/// - The first line of a variant's doc comment becomes its help text.
+///
+/// Fields are positional arguments by default. An `Option<T>` field may be
+/// left out, and a `Vec<T>` field takes any remaining arguments.
+///
+/// - `#[command(named)]` on a field makes it an option given as
+///   `--field value`. A named `bool` field is a flag given as `--field`.
+/// - `#[command(short = 'c')]` on a field also lets it be given as `-c`.
+/// - `#[command(default = "...")]` on a field gives a value to use when it is
+///   left out.
+///
+/// # Examples
+///
+/// ```rust
+/// # #[cfg(feature = "derive")]
+/// # {
+/// use dbgcmd::{Console, DebugCommand};
+///
+/// #[derive(Debug, PartialEq, DebugCommand)]
+/// enum Command {
+///     /// Say hello.
+///     SayHi,
+///
+///     /// Teleport the player.
+///     #[command(name = "tp")]
+///     Teleport {
+///         x: f32,
+///         y: f32,
+///         #[command(short = 'r')]
+///         relative: bool,
+///     },
+/// }
+///
+/// let mut console = Console::new();
+/// console.set_entry("tp 1 2.5".to_owned());
+///
+/// if console.enabled() {
+///     assert_eq!(
+///         console.confirm(),
+///         Ok(Command::Teleport { x: 1.0, y: 2.5, relative: false })
+///     );
+/// }
+///
+/// assert_eq!(
+///     Command::help(),
+///     "say-hi - Say hello.\ntp <x> <y> [--relative] - Teleport the player."
+/// );
+/// # }
+/// ```
+pub trait DebugCommand: FromStr {
+    /// Returns a description of every command.
+    fn commands() -> &'static [CommandInfo];
+
+    /// Returns help text listing every command, one per line.
+    fn help() -> String {
+        let lines: Vec<String> = Self::commands()
+            .iter()
+            .map(|command| match command.help {
+                "" => command.usage(),
+                help => format!("{} - {}", command.usage(), help),
+            })
+            .collect();
+        lines.join("\n")
+    }
+}
+
+/// A description of a single command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CommandInfo {
+    /// The name that the command is entered as.
+    pub name: &'static str,
+
+    /// The arguments the command takes, in order.
+    pub args: &'static [ArgInfo],
+
+    /// A short description of the command.
+    pub help: &'static str,
+}
+
+impl CommandInfo {
+    /// Returns a usage line for the command, like `tp <x> <y> [--speed <f32>]`.
+    pub fn usage(&self) -> String {
+        let mut usage = self.name.to_owned();
+        for arg in self.args {
+            usage.push(' ');
+            usage.push_str(&arg.kind.usage(arg.name, arg.ty));
+        }
+        usage
+    }
+}
+
+/// A description of a single argument of a command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ArgInfo {
+    /// The name of the argument.
+    pub name: &'static str,
+
+    /// The type that the argument is parsed as, as written in the source.
+    pub ty: &'static str,
+
+    /// How the argument is given.
+    pub kind: ArgKind,
+}
@@ -0,0 +1,558 @@
+//! Tab completion of the entry.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt;
+use std::ops::{Range, RangeInclusive};
+use std::path::{Component, Path, PathBuf};
+
+use crate::fuzzy::fuzzy_rank;
+use crate::registry::Registry;
+use crate::tokenize::{tokenize, Token, TokenizeError};
+
+/// The entry being completed, as seen by a `Completer`.
+///
+/// Only the text before the caret is considered. The token the caret is in
+/// (or just after) is the partial token, and completing replaces it.
+#[derive(Debug, Clone)]
+pub struct CompletionContext<'a> {
+    line: &'a str,
+    history: &'a VecDeque<String>,
+    tokens: Vec<Token>,
+    partial: Token,
+}
+
+impl<'a> CompletionContext<'a> {
+    /// Creates a context for completing `line`, which is the entry up to the
+    /// caret. `history` is ordered from most recent to least recent.
+    pub fn new(line: &'a str, history: &'a VecDeque<String>) -> Self {
+        let (mut tokens, end) = match tokenize(line) {
+            Ok(tokens) => (tokens, line.len()),
+            Err(TokenizeError::UnterminatedQuote { quote, .. }) => (
+                tokenize(&format!("{}{}", line, quote)).unwrap_or_default(),
+                line.len(),
+            ),
+            Err(TokenizeError::TrailingBackslash { offset }) => {
+                (tokenize(&line[..offset]).unwrap_or_default(), offset)
+            }
+        };
+        let partial = match tokens.last() {
+            Some(last) if last.span.end >= end && !line[..end].ends_with(char::is_whitespace) => {
+                let mut partial = tokens.pop().unwrap();
+                partial.span.end = line.len();
+                partial
+            }
+            _ => Token {
+                text: String::new(),
+                span: line.len()..line.len(),
+            },
+        };
+        CompletionContext {
+            line,
+            history,
+            tokens,
+            partial,
+        }
+    }
+
+    /// The entry up to the caret.
+    pub fn line(&self) -> &'a str {
+        self.line
+    }
+
+    /// Returns an iterator over the previously confirmed entries, from most
+    /// recent to least recent.
+    pub fn history(&self) -> impl Iterator<Item = &'a str> {
+        self.history.iter().map(String::as_str)
+    }
+
+    /// The complete tokens before the partial token.
+    pub fn tokens(&self) -> &[Token] {
+        &self.tokens
+    }
+
+    /// The index of the partial token in the entry. This is `0` when completing
+    /// the command name, and `1` for its first argument.
+    pub fn index(&self) -> usize {
+        self.tokens.len()
+    }
+
+    /// The text of the partial token, with quotes and escapes removed.
+    pub fn partial(&self) -> &str {
+        &self.partial.text
+    }
+
+    /// The byte range of the partial token in the entry. This is the range
+    /// that is replaced when a candidate is chosen.
+    pub fn span(&self) -> Range<usize> {
+        self.partial.span.clone()
+    }
+}
+
+/// Provides candidates for completing the entry.
+///
+/// Candidates are returned unescaped, and are escaped as needed when inserted
+/// into the entry. They are usually, but not necessarily, extensions of the
+/// partial token.
+///
+/// This is implemented for closures taking a `&CompletionContext`.
+pub trait Completer {
+    fn complete(&mut self, context: &CompletionContext) -> Vec<String>;
+}
+
+impl<F> Completer for F
+where
+    F: FnMut(&CompletionContext) -> Vec<String>,
+{
+    fn complete(&mut self, context: &CompletionContext) -> Vec<String> {
+        self(context)
+    }
+}
+
+/// Provides candidates for a single argument of a command.
+///
+/// This receives the complete tokens before the argument (starting with the
+/// command name), the index of the argument (`0` for the first argument after
+/// the command name), and the partial text of the argument so far.
+///
+/// This is implemented for closures with the same arguments, which is handy
+/// for completing from live data such as entity IDs.
+pub trait ArgCompleter {
+    fn complete(&mut self, tokens: &[Token], index: usize, partial: &str) -> Vec<String>;
+}
+
+impl<F> ArgCompleter for F
+where
+    F: FnMut(&[Token], usize, &str) -> Vec<String>,
+{
+    fn complete(&mut self, tokens: &[Token], index: usize, partial: &str) -> Vec<String> {
+        self(tokens, index, partial)
+    }
+}
+
+/// Completes an argument from a fixed list of choices, such as the variants of
+/// an enum.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Choices {
+    choices: Vec<String>,
+}
+
+impl Choices {
+    pub fn new<I, S>(choices: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Choices {
+            choices: choices.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl ArgCompleter for Choices {
+    fn complete(&mut self, _tokens: &[Token], _index: usize, partial: &str) -> Vec<String> {
+        self.choices
+            .iter()
+            .filter(|choice| choice.starts_with(partial))
+            .cloned()
+            .collect()
+    }
+}
+
+/// Completes an argument with the paths of files and directories under a
+/// root directory. The completed paths are relative to the root, and
+/// directories end in `/`.
+///
+/// Entries starting with `.` are only listed if the partial name does too.
+/// Nothing outside the root is listed, so absolute partials, and partials
+/// containing `..`, have no candidates.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Paths {
+    root: PathBuf,
+}
+
+impl Paths {
+    pub fn new<P: Into<PathBuf>>(root: P) -> Self {
+        Paths { root: root.into() }
+    }
+}
+
+impl ArgCompleter for Paths {
+    fn complete(&mut self, _tokens: &[Token], _index: usize, partial: &str) -> Vec<String> {
+        let (dir, name) = match partial.rfind('/') {
+            Some(i) => partial.split_at(i + 1),
+            None => ("", partial),
+        };
+        let outside_root = Path::new(dir)
+            .components()
+            .any(|component| !matches!(component, Component::Normal(_) | Component::CurDir));
+        if outside_root {
+            return Vec::new();
+        }
+        let entries = match std::fs::read_dir(self.root.join(dir)) {
+            Ok(entries) => entries,
+            Err(_) => return Vec::new(),
+        };
+        let mut candidates: Vec<String> = entries
+            .filter_map(|entry| {
+                let entry = entry.ok()?;
+                let file_name = entry.file_name().into_string().ok()?;
+                if !file_name.starts_with(name)
+                    || file_name.starts_with('.') && !name.starts_with('.')
+                {
+                    return None;
+                }
+                let is_dir = entry.file_type().ok()?.is_dir();
+                Some(format!(
+                    "{}{}{}",
+                    dir,
+                    file_name,
+                    if is_dir { "/" } else { "" }
+                ))
+            })
+            .collect();
+        candidates.sort();
+        candidates
+    }
+}
+
+/// Completes an argument with the integers in a range that start with the
+/// partial number, shortest first. At most `Numbers::LIMIT` candidates are
+/// given, so large ranges are only completed once the number is narrowed down.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Numbers {
+    range: RangeInclusive<i64>,
+}
+
+impl Numbers {
+    /// The maximum number of candidates given at once.
+    pub const LIMIT: usize = 100;
+
+    pub fn new(range: RangeInclusive<i64>) -> Self {
+        Numbers { range }
+    }
+}
+
+impl ArgCompleter for Numbers {
+    fn complete(&mut self, _tokens: &[Token], _index: usize, partial: &str) -> Vec<String> {
+        let (start, end) = (*self.range.start(), *self.range.end());
+        let (negative, digits) = match partial.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, partial),
+        };
+        if digits.is_empty() {
+            return self
+                .range
+                .clone()
+                .filter(|&n| !negative || n < 0)
+                .take(Self::LIMIT)
+                .map(|n| n.to_string())
+                .collect();
+        }
+        let leading_zero = digits.len() > 1 && digits.starts_with('0');
+        let prefix: i64 = match digits.parse() {
+            Ok(prefix) if !leading_zero && digits.bytes().all(|b| b.is_ascii_digit()) => prefix,
+            _ => return Vec::new(),
+        };
+
+        // Numbers starting with `prefix` followed by `k` more digits have
+        // magnitudes from `prefix * 10^k` to `prefix * 10^k + 10^k - 1`.
+        let mut candidates = Vec::new();
+        let mut scale: i64 = 1;
+        while let Some(low) = prefix.checked_mul(scale) {
+            let high = low.saturating_add(scale - 1);
+            let (low, high) = match negative {
+                false => (low.max(start), high.min(end)),
+                true => (
+                    low.max(end.saturating_neg()),
+                    high.min(start.saturating_neg()),
+                ),
+            };
+            for magnitude in low..=high {
+                if candidates.len() >= Self::LIMIT {
+                    return candidates;
+                }
+                candidates.push(format!("{}{}", if negative { "-" } else { "" }, magnitude));
+            }
+            scale = match scale.checked_mul(10) {
+                Some(scale) if prefix != 0 => scale,
+                _ => break,
+            };
+        }
+        candidates
+    }
+}
+
+/// Completes command names, and the arguments of each command.
+///
+/// The command name is completed from the given list of commands first, then
+/// from the commands used in the history. Arguments are completed by the
+/// `ArgCompleter` set for them with `arg`, if there is one, or otherwise from
+/// the arguments previously given to the same command, most recent first.
+///
+/// Candidates must start with the partial token, unless fuzzy matching is
+/// turned on with `set_fuzzy`, in which case they are ranked by `fuzzy_rank`.
+///
+/// # Examples
+///
+/// ```rust
+/// use dbgcmd::{Choices, Console, DefaultCompleter, Numbers};
+///
+/// let mut completer = DefaultCompleter::new(vec!["spawn"]);
+/// completer.arg("spawn", 0, Choices::new(vec!["orc", "ogre", "imp"]));
+/// completer.arg("spawn", 1, Numbers::new(1..=20));
+///
+/// let mut console = Console::new();
+/// console.set_entry("spawn i".to_owned());
+/// console.complete(&mut completer);
+///
+/// if console.enabled() {
+///     assert_eq!(console.entry(), "spawn imp ");
+///
+///     console.receive_char('1');
+///     console.complete(&mut completer);
+///     assert_eq!(console.completions()[..3], ["1", "10", "11"]);
+/// }
+/// ```
+#[derive(Default)]
+pub struct DefaultCompleter {
+    commands: Vec<String>,
+    args: BTreeMap<(String, usize), Box<dyn ArgCompleter>>,
+    fuzzy: bool,
+}
+
+impl fmt::Debug for DefaultCompleter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("DefaultCompleter")
+            .field("commands", &self.commands)
+            .field("args", &self.args.keys().collect::<Vec<_>>())
+            .field("fuzzy", &self.fuzzy)
+            .finish()
+    }
+}
+
+impl DefaultCompleter {
+    pub fn new<I, S>(commands: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        DefaultCompleter {
+            commands: commands.into_iter().map(Into::into).collect(),
+            args: BTreeMap::new(),
+            fuzzy: false,
+        }
+    }
+
+    /// Creates a completer for the commands registered in `registry`.
+    pub fn from_registry<Ctx>(registry: &Registry<Ctx>) -> Self {
+        Self::new(registry.names())
+    }
+
+    /// Sets the completer for an argument of a command, where `index` is `0`
+    /// for the first argument after the command name. This replaces any
+    /// completer previously set for the same argument.
+    pub fn arg<A>(&mut self, command: &str, index: usize, completer: A) -> &mut Self
+    where
+        A: ArgCompleter + 'static,
+    {
+        self.args
+            .insert((command.to_owned(), index), Box::new(completer));
+        self
+    }
+
+    /// Sets whether command names and previously used arguments are matched
+    /// fuzzily. Completers set with `arg` do their own matching.
+    pub fn set_fuzzy(&mut self, fuzzy: bool) -> &mut Self {
+        self.fuzzy = fuzzy;
+        self
+    }
+}
+
+impl Completer for DefaultCompleter {
+    fn complete(&mut self, context: &CompletionContext) -> Vec<String> {
+        let partial = context.partial();
+        let command = context.tokens().first().map(Token::as_str);
+        if let Some(command) = command {
+            let key = (command.to_owned(), context.index() - 1);
+            if let Some(completer) = self.args.get_mut(&key) {
+                return completer.complete(context.tokens(), key.1, partial);
+            }
+        }
+
+        let used = context.history().flat_map(|entry| {
+            let tokens = tokenize(entry).unwrap_or_default();
+            let tokens = match (command, tokens.split_first()) {
+                (None, Some((name, _))) => std::slice::from_ref(name),
+                (Some(command), Some((name, args))) if name.text == command => args,
+                _ => &[],
+            };
+            tokens
+                .iter()
+                .map(|token| token.text.clone())
+                .collect::<Vec<_>>()
+        });
+        let commands = match command {
+            None => self.commands.clone(),
+            Some(_) => Vec::new(),
+        };
+
+        let mut candidates: Vec<String> = Vec::new();
+        for candidate in commands.into_iter().chain(used) {
+            if (self.fuzzy || candidate.starts_with(partial)) && !candidates.contains(&candidate) {
+                candidates.push(candidate);
+            }
+        }
+        if self.fuzzy {
+            candidates = fuzzy_rank(partial, &candidates)
+                .into_iter()
+                .map(|(i, _)| candidates[i].clone())
+                .collect();
+        }
+        candidates
+    }
+}
+
+/// The state of completion between presses of Tab.
+#[cfg(any(debug_assertions, feature = "force-enabled"))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Completion {
+    /// Where the partial token starts in the entry.
+    pub start: usize,
+    pub candidates: Vec<String>,
+    pub selected: Option<usize>,
+}
+
+/// Returns the longest prefix shared by every candidate, on a `char`
+/// boundary.
+#[cfg(any(debug_assertions, feature = "force-enabled"))]
+pub(crate) fn common_prefix(candidates: &[String]) -> &str {
+    let first = match candidates.first() {
+        Some(first) => first.as_str(),
+        None => return "",
+    };
+    let mut len = first.len();
+    for candidate in &candidates[1..] {
+        len = first
+            .char_indices()
+            .zip(candidate.chars())
+            .take_while(|((_, a), b)| a == b)
+            .last()
+            .map_or(0, |((i, a), _)| i + a.len_utf8())
+            .min(len);
+    }
+    &first[..len]
+}
+
+#[cfg(test)]
+#[cfg(any(debug_assertions, feature = "force-enabled"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn context() {
+        let history = VecDeque::new();
+
+        let context = CompletionContext::new("spawn 'big o", &history);
+        assert_eq!(context.index(), 1);
+        assert_eq!(context.partial(), "big o");
+        assert_eq!(context.span(), 6..12);
+
+        let context = CompletionContext::new("spawn orc ", &history);
+        assert_eq!(context.index(), 2);
+        assert_eq!(context.partial(), "");
+        assert_eq!(context.span(), 10..10);
+
+        let context = CompletionContext::new("spawn or\\", &history);
+        assert_eq!(context.partial(), "or");
+        assert_eq!(context.span(), 6..9);
+    }
+
+    #[test]
+    fn default_completer() {
+        let history: VecDeque<String> = vec!["spawn imp 2", "gravity 3", "spawn orc 1"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+        let mut completer = DefaultCompleter::new(vec!["save", "say"]);
+        let complete = |completer: &mut DefaultCompleter, line| {
+            completer.complete(&CompletionContext::new(line, &history))
+        };
+
+        assert_eq!(complete(&mut completer, "s"), vec!["save", "say", "spawn"]);
+        assert_eq!(
+            complete(&mut completer, "spawn "),
+            vec!["imp", "2", "orc", "1"]
+        );
+        assert_eq!(complete(&mut completer, "spawn o"), vec!["orc"]);
+
+        completer.set_fuzzy(true);
+        assert_eq!(complete(&mut completer, "av"), vec!["save", "gravity"]);
+        assert_eq!(complete(&mut completer, "spawn r"), vec!["orc"]);
+        completer.set_fuzzy(false);
+
+        completer.arg("spawn", 1, |tokens: &[Token], index, partial: &str| {
+            vec![format!("{}:{}:{}", tokens[1].text, index, partial)]
+        });
+        assert_eq!(
+            complete(&mut completer, "spawn 'big orc' 3"),
+            vec!["big orc:1:3"]
+        );
+        assert_eq!(complete(&mut completer, "spawn o"), vec!["orc"]);
+    }
+
+    #[test]
+    fn choices() {
+        let mut choices = Choices::new(vec!["easy", "hard", "harder"]);
+        assert_eq!(choices.complete(&[], 0, "har"), vec!["hard", "harder"]);
+        assert!(choices.complete(&[], 0, "x").is_empty());
+    }
+
+    #[test]
+    fn numbers() {
+        let complete = |range, partial| Numbers::new(range).complete(&[], 0, partial);
+
+        assert_eq!(complete(1..=20, "1")[..3], ["1", "10", "11"]);
+        assert_eq!(complete(1..=20, "1").len(), 11);
+        assert_eq!(complete(-12..=5, "-1"), vec!["-1", "-10", "-11", "-12"]);
+        assert_eq!(complete(-3..=5, "-"), vec!["-3", "-2", "-1"]);
+        assert_eq!(complete(0..=100, "0"), vec!["0"]);
+        assert!(complete(0..=100, "01").is_empty());
+        assert!(complete(0..=100, "x").is_empty());
+        assert_eq!(complete(0..=i64::MAX, "").len(), Numbers::LIMIT);
+        assert_eq!(complete(i64::MIN..=-1, "9").len(), 0);
+        assert_eq!(complete(0..=i64::MAX, "9").len(), Numbers::LIMIT);
+    }
+
+    #[test]
+    fn paths() {
+        let root = std::env::temp_dir().join(format!("dbgcmd-paths-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("maps/old")).unwrap();
+        for file in &["maps/castle.map", "maps/cave.map", "maps/.hidden", "readme"] {
+            std::fs::write(root.join(file), "").unwrap();
+        }
+
+        let mut paths = Paths::new(&root);
+        assert_eq!(paths.complete(&[], 0, "m"), vec!["maps/"]);
+        assert_eq!(
+            paths.complete(&[], 0, "maps/"),
+            vec!["maps/castle.map", "maps/cave.map", "maps/old/"]
+        );
+        assert_eq!(paths.complete(&[], 0, "maps/."), vec!["maps/.hidden"]);
+        assert!(paths.complete(&[], 0, "nowhere/").is_empty());
+        assert!(paths.complete(&[], 0, "../").is_empty());
+        assert!(paths.complete(&[], 0, "maps/../").is_empty());
+        assert!(paths.complete(&[], 0, "/").is_empty());
+        assert_eq!(paths.complete(&[], 0, "./r"), vec!["./readme"]);
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn prefixes() {
+        let strings = |list: &[&str]| list.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert_eq!(common_prefix(&strings(&["spawn", "spawé", "spa"])), "spa");
+        assert_eq!(common_prefix(&strings(&["é1", "é2"])), "é");
+        assert_eq!(common_prefix(&strings(&["a", "b"])), "");
+        assert_eq!(common_prefix(&[]), "");
+    }
+}
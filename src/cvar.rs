@@ -0,0 +1,879 @@
+//! Console variables: named, typed settings that can be read and changed from
+//! the console by name.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+
+use crate::error::{Error, ErrorKind};
+use crate::lines;
+use crate::registry::Outcome;
+use crate::tokenize::{quote, tokenize, Token};
+
+/// The value of a cvar.
+///
+/// Enum cvars hold one of their choices as a `Str`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CvarValue {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Str(String),
+}
+
+/// Bools are written as `1` or `0`, and everything else as it is.
+impl fmt::Display for CvarValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CvarValue::Bool(value) => write!(f, "{}", if *value { 1 } else { 0 }),
+            CvarValue::Int(value) => write!(f, "{}", value),
+            CvarValue::Float(value) => write!(f, "{}", value),
+            CvarValue::Str(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+impl From<bool> for CvarValue {
+    fn from(value: bool) -> Self {
+        CvarValue::Bool(value)
+    }
+}
+
+impl From<i64> for CvarValue {
+    fn from(value: i64) -> Self {
+        CvarValue::Int(value)
+    }
+}
+
+impl From<f64> for CvarValue {
+    fn from(value: f64) -> Self {
+        CvarValue::Float(value)
+    }
+}
+
+impl From<&str> for CvarValue {
+    fn from(value: &str) -> Self {
+        CvarValue::Str(value.to_owned())
+    }
+}
+
+impl From<String> for CvarValue {
+    fn from(value: String) -> Self {
+        CvarValue::Str(value)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum CvarType {
+    Bool,
+    Int,
+    Float,
+    Str,
+    Enum(Vec<String>),
+}
+
+type ChangeCallback = Box<dyn FnMut(&CvarValue)>;
+
+/// The definition of a cvar, to be registered with `Cvars::register`.
+///
+/// # Examples
+///
+/// ```rust
+/// use dbgcmd::Cvar;
+///
+/// let gravity = Cvar::float("sv_gravity", 800.0)
+///     .range(0.0, 2000.0)
+///     .description("Downward acceleration, in units per second squared");
+///
+/// let wireframe = Cvar::bool("r_wireframe", false).on_change(|value| {
+///     println!("wireframe is now {}", value);
+/// });
+///
+/// let quality = Cvar::choice("r_quality", &["low", "medium", "high"], "medium");
+/// ```
+pub struct Cvar {
+    name: String,
+    ty: CvarType,
+    default: CvarValue,
+    min: Option<f64>,
+    max: Option<f64>,
+    description: Option<String>,
+    archive: bool,
+    on_change: Option<ChangeCallback>,
+}
+
+impl fmt::Debug for Cvar {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Cvar")
+            .field("name", &self.name)
+            .field("ty", &self.ty)
+            .field("default", &self.default)
+            .field("min", &self.min)
+            .field("max", &self.max)
+            .field("description", &self.description)
+            .field("archive", &self.archive)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Cvar {
+    fn new(name: &str, ty: CvarType, default: CvarValue) -> Self {
+        Cvar {
+            name: name.to_owned(),
+            ty,
+            default,
+            min: None,
+            max: None,
+            description: None,
+            archive: false,
+            on_change: None,
+        }
+    }
+
+    /// A cvar that is either on or off. It can be set with `1`/`0`,
+    /// `true`/`false`, `on`/`off`, or `yes`/`no`.
+    pub fn bool(name: &str, default: bool) -> Self {
+        Cvar::new(name, CvarType::Bool, CvarValue::Bool(default))
+    }
+
+    pub fn int(name: &str, default: i64) -> Self {
+        Cvar::new(name, CvarType::Int, CvarValue::Int(default))
+    }
+
+    pub fn float(name: &str, default: f64) -> Self {
+        Cvar::new(name, CvarType::Float, CvarValue::Float(default))
+    }
+
+    /// A cvar holding any text on a single line. Values with line breaks are
+    /// rejected, since they could not be saved to a config file, so the
+    /// default shouldn't have any either.
+    pub fn string(name: &str, default: &str) -> Self {
+        Cvar::new(name, CvarType::Str, default.into())
+    }
+
+    /// A cvar that can only be set to one of `choices`, ignoring case.
+    pub fn choice(name: &str, choices: &[&str], default: &str) -> Self {
+        let choices = choices.iter().map(|choice| choice.to_string()).collect();
+        Cvar::new(name, CvarType::Enum(choices), default.into())
+    }
+
+    /// Sets the smallest value an int or float cvar can be set to.
+    pub fn min(mut self, min: f64) -> Self {
+        self.min = Some(min);
+        self
+    }
+
+    /// Sets the largest value an int or float cvar can be set to.
+    pub fn max(mut self, max: f64) -> Self {
+        self.max = Some(max);
+        self
+    }
+
+    /// Sets both `min` and `max`.
+    pub fn range(self, min: f64, max: f64) -> Self {
+        self.min(min).max(max)
+    }
+
+    /// Sets the help text shown when the cvar is queried.
+    pub fn description(mut self, description: &str) -> Self {
+        self.description = Some(description.to_owned());
+        self
+    }
+
+    /// Marks the cvar to be written to config files by `Cvars::save`.
+    pub fn archive(mut self) -> Self {
+        self.archive = true;
+        self
+    }
+
+    /// Sets a callback that is run with the new value whenever the value of
+    /// the cvar changes.
+    pub fn on_change<F>(mut self, callback: F) -> Self
+    where
+        F: FnMut(&CvarValue) + 'static,
+    {
+        self.on_change = Some(Box::new(callback));
+        self
+    }
+
+    /// Describes the values this cvar accepts, for error messages.
+    fn expected(&self) -> String {
+        match &self.ty {
+            CvarType::Bool => "bool".to_owned(),
+            CvarType::Int => "int".to_owned(),
+            CvarType::Float => "float".to_owned(),
+            CvarType::Str => "string".to_owned(),
+            CvarType::Enum(choices) => format!("one of {}", choices.join(", ")),
+        }
+    }
+
+    /// Parses `text` as a value for this cvar, and checks it is allowed.
+    fn parse(&self, text: &str) -> Result<CvarValue, ErrorKind> {
+        let value = match &self.ty {
+            CvarType::Bool => match text.to_lowercase().as_str() {
+                "1" | "true" | "on" | "yes" => Some(CvarValue::Bool(true)),
+                "0" | "false" | "off" | "no" => Some(CvarValue::Bool(false)),
+                _ => None,
+            },
+            CvarType::Int => text.parse().ok().map(CvarValue::Int),
+            CvarType::Float => text
+                .parse()
+                .ok()
+                .filter(|value: &f64| value.is_finite())
+                .map(CvarValue::Float),
+            CvarType::Str => Some(text.into()),
+            CvarType::Enum(choices) => choices
+                .iter()
+                .find(|choice| choice.eq_ignore_ascii_case(text))
+                .map(|choice| choice.as_str().into()),
+        };
+        let value = value.ok_or_else(|| ErrorKind::InvalidValue {
+            name: self.name.clone(),
+            value: text.to_owned(),
+            expected: self.expected(),
+        })?;
+        self.check(value)
+    }
+
+    /// Checks that `value` is the right type for this cvar, and within its
+    /// bounds.
+    fn check(&self, value: CvarValue) -> Result<CvarValue, ErrorKind> {
+        let number = match (&self.ty, &value) {
+            (CvarType::Str, CvarValue::Str(text)) if text.contains(['\n', '\r']) => {
+                return Err(ErrorKind::InvalidValue {
+                    name: self.name.clone(),
+                    value: value.to_string(),
+                    expected: "single-line string".to_owned(),
+                })
+            }
+            (CvarType::Bool, CvarValue::Bool(_)) | (CvarType::Str, CvarValue::Str(_)) => None,
+            (CvarType::Int, CvarValue::Int(n)) => Some(*n as f64),
+            (CvarType::Float, CvarValue::Float(n)) if n.is_finite() => Some(*n),
+            (CvarType::Enum(choices), CvarValue::Str(text)) if choices.contains(text) => None,
+            (CvarType::Enum(_), CvarValue::Str(text)) => return self.parse(text),
+            _ => {
+                return Err(ErrorKind::InvalidValue {
+                    name: self.name.clone(),
+                    value: value.to_string(),
+                    expected: self.expected(),
+                })
+            }
+        };
+        let out_of_range = number.is_some_and(|n| {
+            self.min.is_some_and(|min| n < min) || self.max.is_some_and(|max| n > max)
+        });
+        if out_of_range {
+            return Err(ErrorKind::OutOfRange {
+                name: self.name.clone(),
+                value: value.to_string(),
+                min: self.min.map(|min| min.to_string()),
+                max: self.max.map(|max| max.to_string()),
+            });
+        }
+        Ok(value)
+    }
+}
+
+/// A problem with a line of a config file loaded by `Cvars::load`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigDiagnostic {
+    /// The line number, starting from 1.
+    pub line: usize,
+
+    /// The text of the line.
+    pub text: String,
+
+    /// What was wrong with the line. Its span is relative to `text`.
+    pub error: Error,
+}
+
+impl ConfigDiagnostic {
+    /// Renders the error below the line it came from, with carets under the
+    /// part of the line it refers to, like `Error::render`.
+    pub fn render(&self) -> String {
+        format!("line {}: {}", self.line, self.error.render(&self.text))
+    }
+}
+
+impl fmt::Display for ConfigDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.error)
+    }
+}
+
+#[derive(Debug)]
+struct Entry {
+    cvar: Cvar,
+    value: CvarValue,
+    modified: bool,
+}
+
+/// A set of cvars, which can be queried by confirming an entry with just their
+/// name, and set by confirming an entry with their name and a new value, like
+/// `sv_gravity 800`.
+///
+/// Unlike `Registry`, cvars keep working in release mode, so your game can
+/// keep reading them there. Only the console is unable to change them.
+///
+/// # Examples
+///
+/// ```rust
+/// use dbgcmd::{Console, Cvar, Cvars};
+///
+/// let mut cvars = Cvars::new();
+/// cvars.register(Cvar::float("sv_gravity", 800.0).range(0.0, 2000.0));
+///
+/// let mut console = Console::new();
+/// console.set_entry("sv_gravity 600".to_owned());
+///
+/// // Entries that name a cvar are handled, and the rest are parsed as usual
+/// let command = console.confirm_with_cvars::<String>(&mut cvars);
+///
+/// if console.enabled() {
+///     assert!(command.is_none());
+///     assert_eq!(cvars.get_float("sv_gravity"), Some(600.0));
+///     assert!(cvars.take_modified("sv_gravity"));
+///     assert_eq!(console.output().last().unwrap().text, "sv_gravity 600 (default 800)");
+/// }
+/// ```
+#[derive(Debug, Default)]
+pub struct Cvars {
+    cvars: BTreeMap<String, Entry>,
+}
+
+impl Cvars {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a cvar with its default value, replacing any existing cvar
+    /// with the same name.
+    pub fn register(&mut self, cvar: Cvar) {
+        let entry = Entry {
+            value: cvar.default.clone(),
+            cvar,
+            modified: false,
+        };
+        self.cvars.insert(entry.cvar.name.clone(), entry);
+    }
+
+    /// Removes a cvar. Returns `true` if it was registered.
+    pub fn unregister(&mut self, name: &str) -> bool {
+        self.cvars.remove(name).is_some()
+    }
+
+    /// Whether a cvar is registered with the given name.
+    pub fn contains(&self, name: &str) -> bool {
+        self.cvars.contains_key(name)
+    }
+
+    /// Returns an iterator over the names of all registered cvars, in
+    /// alphabetical order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.cvars.keys().map(String::as_str)
+    }
+
+    /// Returns the current value of a cvar.
+    pub fn get(&self, name: &str) -> Option<&CvarValue> {
+        Some(&self.cvars.get(name)?.value)
+    }
+
+    /// Returns the value of a bool cvar, or `None` if there is no bool cvar
+    /// with this name.
+    pub fn get_bool(&self, name: &str) -> Option<bool> {
+        match self.get(name)? {
+            CvarValue::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of an int cvar, or `None` if there is no int cvar
+    /// with this name.
+    pub fn get_int(&self, name: &str) -> Option<i64> {
+        match self.get(name)? {
+            CvarValue::Int(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of a float cvar, or `None` if there is no float cvar
+    /// with this name.
+    pub fn get_float(&self, name: &str) -> Option<f64> {
+        match self.get(name)? {
+            CvarValue::Float(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Returns the value of a string or enum cvar, or `None` if there is no
+    /// such cvar with this name.
+    pub fn get_str(&self, name: &str) -> Option<&str> {
+        match self.get(name)? {
+            CvarValue::Str(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Returns the default value of a cvar.
+    pub fn default_value(&self, name: &str) -> Option<&CvarValue> {
+        Some(&self.cvars.get(name)?.cvar.default)
+    }
+
+    /// Returns the description of a cvar, if it has one.
+    pub fn description(&self, name: &str) -> Option<&str> {
+        self.cvars.get(name)?.cvar.description.as_deref()
+    }
+
+    /// Parses `text` as the new value of a cvar, the same way as when it is
+    /// set from the console, and returns the value it was set to.
+    pub fn set(&mut self, name: &str, text: &str) -> Result<&CvarValue, Error> {
+        let entry = self.entry(name)?;
+        let value = entry
+            .cvar
+            .parse(text)
+            .map_err(|kind| Error::new(kind, None))?;
+        Ok(Cvars::store(entry, value))
+    }
+
+    /// Sets a cvar to a new value, which must be of the cvar's type and
+    /// within its bounds.
+    pub fn set_value<V: Into<CvarValue>>(&mut self, name: &str, value: V) -> Result<(), Error> {
+        let entry = self.entry(name)?;
+        let value = entry
+            .cvar
+            .check(value.into())
+            .map_err(|kind| Error::new(kind, None))?;
+        Cvars::store(entry, value);
+        Ok(())
+    }
+
+    /// Sets a cvar back to its default value. Returns `false` if there is no
+    /// cvar with this name.
+    pub fn reset(&mut self, name: &str) -> bool {
+        match self.cvars.get_mut(name) {
+            Some(entry) => {
+                let value = entry.cvar.default.clone();
+                Cvars::store(entry, value);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Whether a cvar is set to its default value.
+    pub fn is_default(&self, name: &str) -> bool {
+        self.cvars
+            .get(name)
+            .is_some_and(|entry| entry.value == entry.cvar.default)
+    }
+
+    /// Whether a cvar has changed since it was registered, or since its
+    /// modified flag was last cleared by `take_modified`.
+    pub fn is_modified(&self, name: &str) -> bool {
+        self.cvars.get(name).is_some_and(|entry| entry.modified)
+    }
+
+    /// Returns whether a cvar has been modified, and clears its modified flag.
+    /// Call this each frame to react to changes.
+    pub fn take_modified(&mut self, name: &str) -> bool {
+        match self.cvars.get_mut(name) {
+            Some(entry) => std::mem::take(&mut entry.modified),
+            None => false,
+        }
+    }
+
+    /// Returns an iterator over the names of all modified cvars, in
+    /// alphabetical order.
+    pub fn modified(&self) -> impl Iterator<Item = &str> {
+        self.cvars
+            .iter()
+            .filter(|(_, entry)| entry.modified)
+            .map(|(name, _)| name.as_str())
+    }
+
+    fn entry(&mut self, name: &str) -> Result<&mut Entry, Error> {
+        self.cvars.get_mut(name).ok_or_else(|| {
+            Error::new(
+                ErrorKind::UnknownCvar {
+                    name: name.to_owned(),
+                },
+                None,
+            )
+        })
+    }
+
+    /// Stores a checked value, and runs the change callback if it changed.
+    fn store(entry: &mut Entry, value: CvarValue) -> &CvarValue {
+        if entry.value != value {
+            entry.value = value;
+            entry.modified = true;
+            if let Some(callback) = &mut entry.cvar.on_change {
+                callback(&entry.value);
+            }
+        }
+        &entry.value
+    }
+
+    /// Describes the current value of a cvar, as `name value`, followed by its
+    /// default if it has changed, and its description.
+    fn describe(&self, name: &str) -> String {
+        let entry = &self.cvars[name];
+        let mut text = format!("{} {}", name, quote(&entry.value.to_string()));
+        if entry.value != entry.cvar.default {
+            text += &format!(" (default {})", quote(&entry.cvar.default.to_string()));
+        }
+        if let Some(description) = &entry.cvar.description {
+            text += "\n";
+            text += description;
+        }
+        text
+    }
+
+    /// Saves every archived cvar to a config file, as `name value` lines in
+    /// alphabetical order, each after its description as a `#` comment. The
+    /// file is replaced atomically.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use dbgcmd::{Cvar, Cvars};
+    ///
+    /// let mut cvars = Cvars::new();
+    /// cvars.register(Cvar::float("sensitivity", 1.0).archive());
+    /// cvars.register(Cvar::bool("r_wireframe", false));
+    ///
+    /// // Report problems, but carry on with whatever could be loaded
+    /// for diagnostic in cvars.load("config.cfg").unwrap() {
+    ///     eprintln!("config.cfg {}", diagnostic);
+    /// }
+    ///
+    /// cvars.set("sensitivity", "2.5").unwrap();
+    /// cvars.save("config.cfg").unwrap();
+    /// ```
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> std::io::Result<()> {
+        self.write_config(path.as_ref(), false)
+    }
+
+    /// The same as `save`, but only writes archived cvars that are not set to
+    /// their default values, so that changes to the defaults still apply.
+    pub fn save_diff<P: AsRef<Path>>(&self, path: P) -> std::io::Result<()> {
+        self.write_config(path.as_ref(), true)
+    }
+
+    fn write_config(&self, path: &Path, diff: bool) -> std::io::Result<()> {
+        let mut lines = Vec::new();
+        for (name, entry) in &self.cvars {
+            if !entry.cvar.archive || diff && entry.value == entry.cvar.default {
+                continue;
+            }
+            if let Some(description) = &entry.cvar.description {
+                lines.extend(description.lines().map(|line| format!("# {}", line)));
+            }
+            lines.push(format!("{} {}", name, quote(&entry.value.to_string())));
+        }
+        lines::write(path, lines.iter().map(String::as_str))
+    }
+
+    /// Loads a config file written by `save`, setting each cvar it names.
+    ///
+    /// Blank lines, and lines starting with `#` or `//`, are ignored. Any cvar
+    /// can be set, whether or not it is archived. Lines with unknown names or
+    /// invalid values are skipped, and returned as diagnostics, so that one
+    /// bad line doesn't stop the rest of the file from loading.
+    ///
+    /// If the file does not exist, this is treated as an empty file.
+    pub fn load<P: AsRef<Path>>(&mut self, path: P) -> std::io::Result<Vec<ConfigDiagnostic>> {
+        let lines = lines::read(path.as_ref())?;
+        let diagnostics = lines
+            .into_iter()
+            .enumerate()
+            .filter_map(|(i, text)| {
+                let error = self.load_line(&text).err()?;
+                Some(ConfigDiagnostic {
+                    line: i + 1,
+                    text,
+                    error,
+                })
+            })
+            .collect();
+        Ok(diagnostics)
+    }
+
+    fn load_line(&mut self, line: &str) -> Result<(), Error> {
+        let trimmed = line.trim_start();
+        if trimmed.is_empty() || trimmed.starts_with('#') || trimmed.starts_with("//") {
+            return Ok(());
+        }
+        let tokens = tokenize(line)?;
+        match tokens.as_slice() {
+            [name, value] => match self.set(&name.text, &value.text) {
+                Ok(_) => Ok(()),
+                Err(error) => {
+                    let span = match error.kind() {
+                        ErrorKind::UnknownCvar { .. } => &name.span,
+                        _ => &value.span,
+                    };
+                    Err(error.with_span(span.clone()))
+                }
+            },
+            [name] if !self.contains(&name.text) => Err(Error::new(
+                ErrorKind::UnknownCvar {
+                    name: name.text.clone(),
+                },
+                Some(name.span.clone()),
+            )),
+            [_] => Err(Error::new(
+                ErrorKind::MissingArgument {
+                    name: "value".to_owned(),
+                },
+                None,
+            )),
+            [_, _, extra, ..] => Err(Error::new(
+                ErrorKind::ExtraArgument {
+                    value: extra.text.clone(),
+                },
+                Some(extra.span.clone()),
+            )),
+            [] => Ok(()),
+        }
+    }
+
+    /// Runs an entry that starts with the name of a cvar. With no arguments,
+    /// this describes the cvar, and with one, it sets the cvar to it.
+    ///
+    /// Returns `None` if the entry does not start with the name of a cvar, so
+    /// that it can be handled some other way.
+    pub fn dispatch(&mut self, entry: &str) -> Option<Outcome> {
+        let tokens = tokenize(entry).ok()?;
+        let (name, args) = tokens.split_first()?;
+        if !self.contains(&name.text) {
+            return None;
+        }
+        let outcome = match args {
+            [] => Outcome::Output(self.describe(&name.text)),
+            [Token { text, span }] => match self.set(&name.text, text) {
+                Ok(_) => Outcome::Output(self.describe(&name.text)),
+                Err(error) => Outcome::Failed(error.with_span(span.clone())),
+            },
+            [_, extra, ..] => Outcome::Failed(Error::new(
+                ErrorKind::ExtraArgument {
+                    value: extra.text.clone(),
+                },
+                Some(extra.span.clone()),
+            )),
+        };
+        Some(outcome)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cvars() -> Cvars {
+        let mut cvars = Cvars::new();
+        cvars.register(Cvar::bool("r_wireframe", false));
+        cvars.register(Cvar::int("sv_maxplayers", 8).range(1.0, 64.0));
+        cvars.register(
+            Cvar::float("sv_gravity", 800.0)
+                .min(0.0)
+                .description("Downward acceleration"),
+        );
+        cvars.register(Cvar::string("name", "player"));
+        cvars.register(Cvar::choice("r_quality", &["low", "high"], "low"));
+        cvars
+    }
+
+    fn failure(cvars: &mut Cvars, entry: &str) -> String {
+        match cvars.dispatch(entry) {
+            Some(Outcome::Failed(error)) => error.render(entry),
+            outcome => panic!("expected a failure, got {:?}", outcome),
+        }
+    }
+
+    #[test]
+    fn typed_values() {
+        let mut cvars = cvars();
+        assert_eq!(
+            cvars.set("r_wireframe", "ON").unwrap(),
+            &CvarValue::Bool(true)
+        );
+        assert_eq!(cvars.get_bool("r_wireframe"), Some(true));
+        assert_eq!(cvars.get_int("r_wireframe"), None);
+
+        cvars.set("sv_maxplayers", "16").unwrap();
+        assert_eq!(cvars.get_int("sv_maxplayers"), Some(16));
+        cvars.set_value("sv_gravity", 9.8).unwrap();
+        assert_eq!(cvars.get_float("sv_gravity"), Some(9.8));
+        cvars.set("r_quality", "HIGH").unwrap();
+        assert_eq!(cvars.get_str("r_quality"), Some("high"));
+        cvars.set_value("name", "big orc").unwrap();
+        assert_eq!(cvars.get("name").unwrap().to_string(), "big orc");
+
+        assert!(cvars.set_value("sv_gravity", f64::NAN).is_err());
+        assert!(cvars.set_value("sv_maxplayers", 2.0).is_err());
+        assert_eq!(
+            cvars.set("sv_maxplayers", "100").unwrap_err().to_string(),
+            "value 100 for sv_maxplayers is out of range (1 to 64)"
+        );
+        assert_eq!(
+            cvars.set("fov", "90").unwrap_err().kind(),
+            &ErrorKind::UnknownCvar { name: "fov".into() }
+        );
+
+        assert!(!cvars.is_default("sv_gravity"));
+        assert!(cvars.reset("sv_gravity"));
+        assert!(cvars.is_default("sv_gravity"));
+        assert_eq!(cvars.default_value("r_quality"), Some(&"low".into()));
+    }
+
+    #[test]
+    fn modified_flags_and_callbacks() {
+        use std::cell::RefCell;
+        use std::rc::Rc;
+
+        let changes = Rc::new(RefCell::new(Vec::new()));
+        let mut cvars = cvars();
+        cvars.register(Cvar::int("fps_max", 60).on_change({
+            let changes = changes.clone();
+            move |value| changes.borrow_mut().push(value.clone())
+        }));
+
+        cvars.set("fps_max", "60").unwrap();
+        assert!(!cvars.is_modified("fps_max"));
+        cvars.set("fps_max", "144").unwrap();
+        cvars.set("name", "imp").unwrap();
+        assert_eq!(
+            cvars.modified().collect::<Vec<_>>(),
+            vec!["fps_max", "name"]
+        );
+
+        assert!(cvars.take_modified("fps_max"));
+        assert!(!cvars.take_modified("fps_max"));
+        cvars.reset("fps_max");
+        assert!(cvars.is_modified("fps_max"));
+        assert_eq!(
+            *changes.borrow(),
+            vec![CvarValue::Int(144), CvarValue::Int(60)]
+        );
+    }
+
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!("dbgcmd-{}-{}", std::process::id(), name));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    fn archived() -> Cvars {
+        let mut cvars = Cvars::new();
+        cvars.register(
+            Cvar::float("sensitivity", 1.0)
+                .min(0.1)
+                .description("Mouse sensitivity")
+                .archive(),
+        );
+        cvars.register(Cvar::string("name", "player").archive());
+        cvars.register(Cvar::bool("r_wireframe", false));
+        cvars
+    }
+
+    #[test]
+    fn save_and_load() {
+        let path = temp_path("save_and_load_cvars");
+        let mut cvars = archived();
+        cvars.set("name", "big \\ orc").unwrap();
+        cvars.set("r_wireframe", "1").unwrap();
+
+        cvars.save(&path).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "name \"big \\\\ orc\"\n# Mouse sensitivity\nsensitivity 1\n"
+        );
+        cvars.save_diff(&path).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "name \"big \\\\ orc\"\n"
+        );
+
+        let mut loaded = archived();
+        assert_eq!(loaded.load(&path).unwrap(), vec![]);
+        assert_eq!(loaded.get_str("name"), Some("big \\ orc"));
+        assert!(loaded.is_default("r_wireframe"));
+
+        assert_eq!(
+            loaded.set("name", "two\nlines").unwrap_err().to_string(),
+            "invalid value \"two\\nlines\" for argument name (expected single-line string)"
+        );
+        assert!(loaded.set_value("name", "carriage\rreturn").is_err());
+        loaded.save(&path).unwrap();
+        let mut reloaded = archived();
+        assert_eq!(reloaded.load(&path).unwrap(), vec![]);
+        assert_eq!(reloaded.get_str("name"), Some("big \\ orc"));
+
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded.load(&path).unwrap(), vec![]);
+    }
+
+    #[test]
+    fn load_diagnostics() {
+        let path = temp_path("load_diagnostics");
+        std::fs::write(
+            &path,
+            "# comment\n\n  // another\nfov 90\nsensitivity 0\nname\nr_wireframe on\nname a b\nname 'C:\\new\\rest'\n",
+        )
+        .unwrap();
+
+        let mut cvars = archived();
+        let diagnostics: Vec<String> = cvars
+            .load(&path)
+            .unwrap()
+            .iter()
+            .map(ConfigDiagnostic::render)
+            .collect();
+        assert_eq!(
+            diagnostics,
+            vec![
+                "line 4: fov 90\n^^^ unknown cvar \"fov\"",
+                "line 5: sensitivity 0\n            ^ value 0 for sensitivity is out of range (at least 0.1)",
+                "line 6: name\n    ^ missing argument value",
+                "line 8: name a b\n       ^ unexpected argument \"b\"",
+            ]
+        );
+        assert_eq!(cvars.get_bool("r_wireframe"), Some(true));
+        assert_eq!(cvars.get_float("sensitivity"), Some(1.0));
+        assert_eq!(cvars.get_str("name"), Some("C:\\new\\rest"));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn dispatch() {
+        let mut cvars = cvars();
+        assert_eq!(cvars.dispatch("spawn orc"), None);
+        assert_eq!(cvars.dispatch(""), None);
+        assert_eq!(
+            cvars.dispatch("sv_gravity"),
+            Some(Outcome::Output(
+                "sv_gravity 800\nDownward acceleration".into()
+            ))
+        );
+        assert_eq!(
+            cvars.dispatch("name 'big orc'"),
+            Some(Outcome::Output("name \"big orc\" (default player)".into()))
+        );
+        assert_eq!(cvars.get_str("name"), Some("big orc"));
+
+        assert_eq!(
+            failure(&mut cvars, "r_quality ultra"),
+            "r_quality ultra\n          ^^^^^ invalid value \"ultra\" for argument r_quality \
+             (expected one of low, high)"
+        );
+        assert_eq!(
+            failure(&mut cvars, "sv_gravity -1"),
+            "sv_gravity -1\n           ^^ value -1 for sv_gravity is out of range (at least 0)"
+        );
+        assert_eq!(
+            failure(&mut cvars, "r_wireframe 1 2"),
+            "r_wireframe 1 2\n              ^ unexpected argument \"2\""
+        );
+    }
+}
@@ -0,0 +1,271 @@
+//! An error type for entries that could not be run as commands.
+
+use std::fmt;
+use std::ops::Range;
+
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::args::ArgError;
+use crate::tokenize::TokenizeError;
+
+/// What went wrong with an entry.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ErrorKind {
+    /// The entry was empty, or only whitespace.
+    Empty,
+
+    /// No command has this name.
+    UnknownCommand { name: String },
+
+    /// A required argument was not given.
+    MissingArgument { name: String },
+
+    /// An argument could not be parsed as the expected type.
+    InvalidValue {
+        name: String,
+        value: String,
+        expected: String,
+    },
+
+    /// More arguments were given than the command takes.
+    ExtraArgument { value: String },
+
+    /// An option was given that the command does not have.
+    UnknownOption { option: String },
+
+    /// An option that takes a value was given without one.
+    MissingValue { option: String },
+
+    /// A value was outside the range allowed for it. At least one of `min` and
+    /// `max` is set.
+    OutOfRange {
+        name: String,
+        value: String,
+        min: Option<String>,
+        max: Option<String>,
+    },
+
+    /// No cvar has this name.
+    UnknownCvar { name: String },
+
+    /// An alias expanded, directly or through other aliases, into itself.
+    /// `names` are the aliases in the order they were expanded, ending with
+    /// the repeated one.
+    AliasCycle { names: Vec<String> },
+
+    /// A quote was opened but never closed.
+    UnterminatedQuote { quote: char },
+
+    /// The entry ended with a backslash that escapes nothing.
+    TrailingBackslash,
+
+    /// Any other error, such as one from your own `FromStr` implementation.
+    Custom(String),
+}
+
+/// An error from parsing or running an entry, with the byte range of the entry
+/// that it refers to.
+///
+/// Tokenizing and argument errors convert into this type, as do strings, so
+/// your own `FromStr` errors can be wrapped with `Error::custom` or `into()`.
+///
+/// # Examples
+///
+/// ```rust
+/// use dbgcmd::{Error, ErrorKind};
+///
+/// let error = Error::new(
+///     ErrorKind::UnknownCommand { name: "fly".to_owned() },
+///     Some(0..3),
+/// );
+///
+/// assert_eq!(error.render("fly 10"), "fly 10\n^^^ unknown command \"fly\"");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Error {
+    kind: ErrorKind,
+    span: Option<Range<usize>>,
+}
+
+impl Error {
+    pub fn new(kind: ErrorKind, span: Option<Range<usize>>) -> Self {
+        Error { kind, span }
+    }
+
+    /// Wraps any displayable error, with no span.
+    pub fn custom<E: fmt::Display>(error: E) -> Self {
+        Error::new(ErrorKind::Custom(error.to_string()), None)
+    }
+
+    /// Sets the byte range of the entry that the error refers to.
+    pub fn with_span(mut self, span: Range<usize>) -> Self {
+        self.span = Some(span);
+        self
+    }
+
+    pub fn kind(&self) -> &ErrorKind {
+        &self.kind
+    }
+
+    /// The byte range of the entry that the error refers to, if known.
+    pub fn span(&self) -> Option<Range<usize>> {
+        self.span.clone()
+    }
+
+    /// Renders the error below the entry it came from, with carets under the
+    /// part of the entry it refers to.
+    ///
+    /// If the error has no span, a single caret is drawn just past the end of
+    /// the entry. A span that doesn't fall on character boundaries is widened
+    /// to the characters it touches.
+    pub fn render(&self, entry: &str) -> String {
+        let span = self.span.clone().unwrap_or(entry.len()..entry.len());
+        let mut start = span.start.min(entry.len());
+        while !entry.is_char_boundary(start) {
+            start -= 1;
+        }
+        let mut end = span.end.clamp(start, entry.len());
+        while !entry.is_char_boundary(end) {
+            end += 1;
+        }
+        let indent = entry[..start].graphemes(true).count();
+        let width = entry[start..end].graphemes(true).count().max(1);
+        format!(
+            "{}\n{}{} {}",
+            entry,
+            " ".repeat(indent),
+            "^".repeat(width),
+            self
+        )
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.kind {
+            ErrorKind::Empty => write!(f, "no command entered"),
+            ErrorKind::UnknownCommand { name } => write!(f, "unknown command {:?}", name),
+            ErrorKind::MissingArgument { name } => write!(f, "missing argument {}", name),
+            ErrorKind::InvalidValue {
+                name,
+                value,
+                expected,
+            } => write!(
+                f,
+                "invalid value {:?} for argument {} (expected {})",
+                value, name, expected
+            ),
+            ErrorKind::ExtraArgument { value } => write!(f, "unexpected argument {:?}", value),
+            ErrorKind::UnknownOption { option } => write!(f, "unknown option {}", option),
+            ErrorKind::MissingValue { option } => write!(f, "missing value for option {}", option),
+            ErrorKind::OutOfRange {
+                name,
+                value,
+                min,
+                max,
+            } => {
+                write!(f, "value {} for {} is out of range ", value, name)?;
+                match (min, max) {
+                    (Some(min), Some(max)) => write!(f, "({} to {})", min, max),
+                    (Some(min), None) => write!(f, "(at least {})", min),
+                    (None, Some(max)) => write!(f, "(at most {})", max),
+                    (None, None) => Ok(()),
+                }
+            }
+            ErrorKind::UnknownCvar { name } => write!(f, "unknown cvar {:?}", name),
+            ErrorKind::AliasCycle { names } => {
+                write!(f, "alias cycle: {}", names.join(" -> "))
+            }
+            ErrorKind::UnterminatedQuote { quote } => write!(f, "unterminated {} quote", quote),
+            ErrorKind::TrailingBackslash => write!(f, "trailing backslash"),
+            ErrorKind::Custom(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<TokenizeError> for Error {
+    fn from(error: TokenizeError) -> Self {
+        let offset = error.offset();
+        let kind = match error {
+            TokenizeError::UnterminatedQuote { quote, .. } => {
+                ErrorKind::UnterminatedQuote { quote }
+            }
+            TokenizeError::TrailingBackslash { .. } => ErrorKind::TrailingBackslash,
+        };
+        Error::new(kind, Some(offset..offset + 1))
+    }
+}
+
+impl From<ArgError> for Error {
+    fn from(error: ArgError) -> Self {
+        let span = error.span();
+        let kind = match error {
+            ArgError::Missing { name } => ErrorKind::MissingArgument { name },
+            ArgError::Invalid {
+                name,
+                value,
+                expected,
+                ..
+            } => ErrorKind::InvalidValue {
+                name,
+                value,
+                expected,
+            },
+            ArgError::Unexpected { value, .. } => ErrorKind::ExtraArgument { value },
+            ArgError::UnknownOption { option, .. } => ErrorKind::UnknownOption { option },
+            ArgError::MissingValue { option, .. } => ErrorKind::MissingValue { option },
+        };
+        Error::new(kind, span)
+    }
+}
+
+impl From<std::convert::Infallible> for Error {
+    fn from(error: std::convert::Infallible) -> Self {
+        match error {}
+    }
+}
+
+impl From<String> for Error {
+    fn from(message: String) -> Self {
+        Error::new(ErrorKind::Custom(message), None)
+    }
+}
+
+impl From<&str> for Error {
+    fn from(message: &str) -> Self {
+        Error::from(message.to_owned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rendering() {
+        let error = Error::from(TokenizeError::UnterminatedQuote {
+            quote: '"',
+            offset: 4,
+        });
+        assert_eq!(
+            error.render("say \"hi"),
+            "say \"hi\n    ^ unterminated \" quote"
+        );
+
+        let error = Error::from(ArgError::Missing { name: "y".into() });
+        assert_eq!(error.render("tp 1"), "tp 1\n    ^ missing argument y");
+
+        let error = Error::custom("no such item").with_span(5..10);
+        assert_eq!(
+            error.render("give wörd"),
+            "give wörd\n     ^^^^ no such item"
+        );
+
+        let error = Error::custom("bad").with_span(1..2);
+        assert_eq!(error.render("é"), "é\n^ bad");
+        let error = Error::custom("bad").with_span(3..20);
+        assert_eq!(error.render("aéé"), "aéé\n  ^ bad");
+    }
+}
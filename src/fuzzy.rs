@@ -0,0 +1,219 @@
+//! Fuzzy matching of text against a pattern, for completion and history
+//! search.
+
+/// The score given for each matched character.
+const MATCH: i64 = 16;
+
+/// The extra score for matching the first character of a word.
+const WORD_START: i64 = 8;
+
+/// The extra score for matching the character right after the previous match.
+const CONSECUTIVE: i64 = 12;
+
+/// The penalty for each character skipped between or before matches.
+const GAP: i64 = 1;
+
+/// How well a pattern matched some text.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FuzzyMatch {
+    /// The score of the match. Higher is better.
+    pub score: i64,
+
+    /// The indices of the matched characters in the text, counted in `char`s,
+    /// in ascending order.
+    pub indices: Vec<usize>,
+}
+
+fn is_separator(ch: char) -> bool {
+    ch.is_whitespace() || matches!(ch, '_' | '-' | '/' | '.' | ':' | '\\')
+}
+
+/// Matches `pattern` against `text` as a subsequence, ignoring case.
+///
+/// Every character of the pattern must appear in the text in the same order,
+/// but not necessarily next to each other. Matches at the start of words (after
+/// a separator like `_` or `/`, or a lowercase-to-uppercase change) and runs of
+/// consecutive characters score higher, and skipped characters score lower.
+/// Of all the ways the pattern could match, the highest scoring one is chosen.
+///
+/// Returns `None` if the text does not contain the pattern. An empty pattern
+/// matches everything with a score of `0`.
+///
+/// # Examples
+///
+/// ```rust
+/// use dbgcmd::fuzzy_match;
+///
+/// let matched = fuzzy_match("seac", "spawn_enemy_at_cursor").unwrap();
+/// assert_eq!(matched.indices, vec![0, 6, 12, 15]);
+///
+/// assert!(fuzzy_match("scan", "spawn_enemy_at_cursor").is_none());
+/// ```
+pub fn fuzzy_match(pattern: &str, text: &str) -> Option<FuzzyMatch> {
+    let pattern: Vec<char> = pattern.chars().flat_map(char::to_lowercase).collect();
+    let text: Vec<char> = text.chars().collect();
+    if pattern.is_empty() {
+        return Some(FuzzyMatch {
+            score: 0,
+            indices: Vec::new(),
+        });
+    }
+    if pattern.len() > text.len() {
+        return None;
+    }
+
+    let lower: Vec<char> = text
+        .iter()
+        .map(|ch| ch.to_lowercase().next().unwrap_or(*ch))
+        .collect();
+    let bonus: Vec<i64> = (0..text.len())
+        .map(|j| {
+            let word_start = j == 0
+                || is_separator(text[j - 1]) && !is_separator(text[j])
+                || text[j - 1].is_lowercase() && text[j].is_uppercase();
+            if word_start {
+                WORD_START
+            } else {
+                0
+            }
+        })
+        .collect();
+
+    // `scores[i][j]` is the best score for matching the first `i + 1`
+    // characters of the pattern, with the last of them at `text[j]`. `from`
+    // records where the previous pattern character was matched.
+    let (n, m) = (pattern.len(), text.len());
+    let mut scores = vec![vec![None; m]; n];
+    let mut from = vec![vec![0; m]; n];
+
+    for j in 0..m {
+        if lower[j] == pattern[0] {
+            scores[0][j] = Some(MATCH + bonus[j] - GAP * j as i64);
+        }
+    }
+    for i in 1..n {
+        // The best score for the previous character matched at least two
+        // characters back, minus the gap up to `j`, and where it was.
+        let mut best: Option<(i64, usize)> = None;
+        for j in 1..m {
+            if let Some((score, k)) = best {
+                best = Some((score - GAP, k));
+            }
+            if j >= 2 {
+                match (scores[i - 1][j - 2], best) {
+                    (Some(score), Some((best, _))) if best >= score - GAP => (),
+                    (Some(score), _) => best = Some((score - GAP, j - 2)),
+                    (None, _) => (),
+                }
+            }
+            if lower[j] != pattern[i] {
+                continue;
+            }
+            let adjacent = scores[i - 1][j - 1].map(|score| (score + CONSECUTIVE, j - 1));
+            let previous = match (adjacent, best) {
+                (Some(adjacent), Some(best)) if best.0 > adjacent.0 => Some(best),
+                (Some(adjacent), _) => Some(adjacent),
+                (None, best) => best,
+            };
+            if let Some((score, k)) = previous {
+                scores[i][j] = Some(score + MATCH + bonus[j]);
+                from[i][j] = k;
+            }
+        }
+    }
+
+    let (mut j, score) = (0..m)
+        .filter_map(|j| scores[n - 1][j].map(|score| (j, score)))
+        .fold(None, |best: Option<(usize, i64)>, (j, score)| match best {
+            Some((_, best_score)) if best_score >= score => best,
+            _ => Some((j, score)),
+        })?;
+    let mut indices = vec![0; n];
+    for i in (0..n).rev() {
+        indices[i] = j;
+        j = from[i][j];
+    }
+
+    Some(FuzzyMatch { score, indices })
+}
+
+/// Matches `pattern` against each of `candidates` with `fuzzy_match`, and
+/// returns the index and match of each one that matched, best first.
+///
+/// Candidates with equal scores are ordered by length, shortest first, then by
+/// their original order, so the ranking is always the same for the same input.
+///
+/// # Examples
+///
+/// ```rust
+/// use dbgcmd::fuzzy_rank;
+///
+/// let candidates = ["set_time", "spawn_enemy_at_cursor", "save", "spawn_enemy"];
+/// let ranked: Vec<usize> = fuzzy_rank("spen", &candidates)
+///     .into_iter()
+///     .map(|(index, _)| index)
+///     .collect();
+///
+/// assert_eq!(ranked, vec![3, 1]);
+/// ```
+pub fn fuzzy_rank<S: AsRef<str>>(pattern: &str, candidates: &[S]) -> Vec<(usize, FuzzyMatch)> {
+    let mut ranked: Vec<(usize, FuzzyMatch)> = candidates
+        .iter()
+        .enumerate()
+        .filter_map(|(i, candidate)| Some((i, fuzzy_match(pattern, candidate.as_ref())?)))
+        .collect();
+    ranked.sort_by(|(a, a_match), (b, b_match)| {
+        b_match
+            .score
+            .cmp(&a_match.score)
+            .then_with(|| {
+                candidates[*a]
+                    .as_ref()
+                    .len()
+                    .cmp(&candidates[*b].as_ref().len())
+            })
+            .then_with(|| a.cmp(b))
+    });
+    ranked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn indices(pattern: &str, text: &str) -> Option<Vec<usize>> {
+        fuzzy_match(pattern, text).map(|matched| matched.indices)
+    }
+
+    #[test]
+    fn matches_subsequences() {
+        assert_eq!(indices("abc", "aXbXc"), Some(vec![0, 2, 4]));
+        assert_eq!(indices("ABC", "abc"), Some(vec![0, 1, 2]));
+        assert_eq!(indices("cab", "abc"), None);
+        assert_eq!(indices("abcd", "abc"), None);
+        assert_eq!(indices("", "abc"), Some(vec![]));
+        assert_eq!(indices("é", "café"), Some(vec![3]));
+    }
+
+    #[test]
+    fn prefers_word_starts_and_runs() {
+        assert_eq!(indices("ec", "spawn_enemy_at_cursor"), Some(vec![6, 15]));
+        assert_eq!(indices("sp", "sxp_sp"), Some(vec![4, 5]));
+        assert_eq!(indices("gm", "toggleGodMode"), Some(vec![6, 9]));
+        assert_eq!(indices("map", "m_a_p map"), Some(vec![6, 7, 8]));
+
+        let word_start = fuzzy_match("e", "x_e").unwrap().score;
+        let middle = fuzzy_match("e", "xxe").unwrap().score;
+        assert!(word_start > middle);
+    }
+
+    #[test]
+    fn ranking_is_stable() {
+        let candidates = ["bb", "ab", "ba", "b"];
+        let ranked: Vec<usize> = fuzzy_rank("b", &candidates)
+            .into_iter()
+            .map(|(i, _)| i)
+            .collect();
+        assert_eq!(ranked, vec![3, 0, 2, 1]);
+    }
+}
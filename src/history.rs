@@ -0,0 +1,47 @@
+//! Options controlling how the command history is kept.
+
+/// Controls which confirmed entries are stored in the history of a `Console`,
+/// and how many of them are kept.
+///
+/// The default keeps every entry forever, which matches the behaviour of
+/// `Console` when no options are set.
+///
+/// # Examples
+///
+/// ```rust
+/// use dbgcmd::{Console, HistoryOptions};
+///
+/// let mut console = Console::new();
+/// console.set_history_options(HistoryOptions {
+///     max_len: Some(500),
+///     ignore_dups: true,
+///     ..Default::default()
+/// });
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct HistoryOptions {
+    /// The maximum number of entries to keep. Once this is exceeded, the oldest
+    /// entries are evicted. `None` means there is no limit.
+    pub max_len: Option<usize>,
+
+    /// Don't store entries that are empty or only whitespace.
+    pub ignore_empty: bool,
+
+    /// Don't store entries that are identical to the most recent entry.
+    pub ignore_dups: bool,
+
+    /// Don't store entries that start with a space, like bash's
+    /// `HISTCONTROL=ignorespace`.
+    pub ignore_space: bool,
+}
+
+impl HistoryOptions {
+    /// Whether `entry` should be stored, given the most recent entry in the
+    /// history.
+    #[cfg(any(debug_assertions, feature = "force-enabled"))]
+    pub(crate) fn should_store(&self, entry: &str, previous: Option<&str>) -> bool {
+        !(self.ignore_empty && entry.trim().is_empty()
+            || self.ignore_dups && previous == Some(entry)
+            || self.ignore_space && entry.starts_with(' '))
+    }
+}
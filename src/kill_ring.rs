@@ -0,0 +1,120 @@
+//! The kill ring, which keeps text removed from the entry so it can be
+//! yanked back in later.
+
+use std::collections::VecDeque;
+
+/// The default number of kills kept by a `Console`.
+pub(crate) const DEFAULT_KILL_RING_CAPACITY: usize = 32;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct KillRing {
+    kills: VecDeque<String>,
+    capacity: usize,
+    yank_index: usize,
+}
+
+impl Default for KillRing {
+    fn default() -> Self {
+        KillRing {
+            kills: VecDeque::new(),
+            capacity: DEFAULT_KILL_RING_CAPACITY,
+            yank_index: 0,
+        }
+    }
+}
+
+impl KillRing {
+    /// Adds a new kill to the front of the ring.
+    pub fn push(&mut self, text: String) {
+        if self.capacity == 0 {
+            return;
+        }
+        self.kills.push_front(text);
+        self.kills.truncate(self.capacity);
+    }
+
+    /// Adds text to the end of the most recent kill, for consecutive kills
+    /// moving forwards through the entry.
+    pub fn append(&mut self, text: &str) {
+        match self.kills.front_mut() {
+            Some(kill) => kill.push_str(text),
+            None => self.push(text.to_owned()),
+        }
+    }
+
+    /// Adds text to the start of the most recent kill, for consecutive kills
+    /// moving backwards through the entry.
+    pub fn prepend(&mut self, text: &str) {
+        match self.kills.front_mut() {
+            Some(kill) => kill.insert_str(0, text),
+            None => self.push(text.to_owned()),
+        }
+    }
+
+    /// Returns the most recent kill, and resets the ring so that `rotate`
+    /// continues from there.
+    pub fn yank(&mut self) -> Option<&str> {
+        self.yank_index = 0;
+        self.kills.front().map(String::as_str)
+    }
+
+    /// Moves on to the next older kill, wrapping back around to the most
+    /// recent one, and returns it.
+    pub fn rotate(&mut self) -> Option<&str> {
+        if self.kills.is_empty() {
+            return None;
+        }
+        self.yank_index = (self.yank_index + 1) % self.kills.len();
+        Some(&self.kills[self.yank_index])
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.kills.iter().map(String::as_str)
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        self.kills.truncate(capacity);
+        self.yank_index = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rotation_wraps() {
+        let mut ring = KillRing::default();
+        assert_eq!(ring.yank(), None);
+        assert_eq!(ring.rotate(), None);
+
+        ring.push("a".into());
+        ring.push("b".into());
+        ring.push("c".into());
+
+        assert_eq!(ring.yank(), Some("c"));
+        assert_eq!(ring.rotate(), Some("b"));
+        assert_eq!(ring.rotate(), Some("a"));
+        assert_eq!(ring.rotate(), Some("c"));
+        assert_eq!(ring.yank(), Some("c"));
+    }
+
+    #[test]
+    fn capacity_drops_oldest() {
+        let mut ring = KillRing::default();
+        ring.set_capacity(2);
+
+        ring.push("a".into());
+        ring.push("b".into());
+        ring.push("c".into());
+        ring.append("!");
+        ring.prepend("<");
+
+        assert_eq!(ring.iter().collect::<Vec<_>>(), vec!["<c!", "b"]);
+    }
+}
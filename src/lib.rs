@@ -1,7 +1,7 @@
 //! This is a simple library for implementing command-line-style debug consoles within an application.
 //!
 //! It doesn't handle rendering, or the logic of any individual commands. All it does is model the
-//! state of the console, including command history, and whether or not it is active. You define
+//! state of the console, including command history, output, and whether or not it is active. You define
 //! your own command struct/enum, and drive the inputs to the console. After that, you can use the
 //! `confirm` method to parse the text entered so far into a command.
 //!
@@ -48,6 +48,33 @@
 //! }
 //! ```
 //!
+//! ## Errors
+//!
+//! ```rust
+//! use std::str::FromStr;
+//! use dbgcmd::{Console, Error};
+//!
+//! #[derive(Debug)]
+//! struct Speed(f32);
+//!
+//! impl FromStr for Speed {
+//!     type Err = Error;
+//!
+//!     fn from_str(s: &str) -> Result<Self, Self::Err> {
+//!         s.parse().map(Speed).map_err(|e| Error::custom(e).with_span(0..s.len()))
+//!     }
+//! }
+//!
+//! let mut console = Console::new();
+//! console.set_entry("fast".to_owned());
+//!
+//! if console.enabled() {
+//!     // Errors are rendered with carets under the offending part of the entry
+//!     let message = console.confirm_rendered::<Speed>().unwrap_err();
+//!     assert_eq!(message, "fast\n^^^^ invalid float literal");
+//! }
+//! ```
+//!
 //! ## History
 //!
 //! ```rust
@@ -86,18 +113,112 @@
 //! }
 //! ```
 #[cfg(any(debug_assertions, feature = "force-enabled"))]
-use std::collections::VecDeque;
+mod alias;
+mod args;
+mod clipboard;
+mod command;
+mod complete;
+mod cvar;
+mod error;
+mod fuzzy;
+mod history;
+#[cfg(any(debug_assertions, feature = "force-enabled"))]
+mod kill_ring;
+mod lines;
+#[cfg(feature = "log")]
+mod logger;
+mod output;
+mod registry;
+#[cfg(any(debug_assertions, feature = "force-enabled"))]
+mod search;
+#[cfg(any(debug_assertions, feature = "force-enabled"))]
+mod suggest;
+mod text;
+mod tokenize;
+#[cfg(feature = "tracing")]
+mod trace;
+#[cfg(any(debug_assertions, feature = "force-enabled"))]
+mod undo;
+
+pub use args::{ArgError, ArgKind, Args, Signature};
+pub use clipboard::{Clipboard, MemoryClipboard};
+pub use command::{ArgInfo, CommandInfo, DebugCommand};
+pub use complete::{
+    ArgCompleter, Choices, Completer, CompletionContext, DefaultCompleter, Numbers, Paths,
+};
+pub use cvar::{ConfigDiagnostic, Cvar, CvarValue, Cvars};
+pub use error::{Error, ErrorKind};
+pub use fuzzy::{fuzzy_match, fuzzy_rank, FuzzyMatch};
+pub use history::HistoryOptions;
+#[cfg(feature = "log")]
+pub use logger::{ConsoleLogger, LogFilter};
+pub use output::{Level, OutputLine, OutputOptions};
+pub use registry::{Outcome, Registry};
+pub use text::WordBoundary;
+pub use tokenize::{tokenize, Token, TokenizeError};
+#[cfg(feature = "tracing")]
+pub use trace::{ConsoleLayer, TraceEvent, TraceFilter, TraceKind};
+
+#[cfg(feature = "derive")]
+pub use dbgcmd_derive::DebugCommand;
+
+use std::ops::Range;
+
+#[cfg(any(debug_assertions, feature = "force-enabled"))]
+use std::collections::{BTreeMap, VecDeque};
 
 #[cfg(any(debug_assertions, feature = "force-enabled"))]
 use itertools::Itertools;
 
+#[cfg(any(debug_assertions, feature = "force-enabled"))]
+use complete::Completion;
+
+#[cfg(any(debug_assertions, feature = "force-enabled"))]
+use kill_ring::KillRing;
+
+#[cfg(any(debug_assertions, feature = "force-enabled"))]
+use output::Scrollback;
+
+#[cfg(any(debug_assertions, feature = "force-enabled"))]
+use search::Search;
+
+#[cfg(any(debug_assertions, feature = "force-enabled"))]
+use suggest::Suggester;
+
+#[cfg(any(debug_assertions, feature = "force-enabled"))]
+use undo::{EditKind, Snapshot, UndoStack};
+
 #[derive(Default, Clone, PartialEq, Eq)]
 #[cfg(any(debug_assertions, feature = "force-enabled"))]
 pub struct Console {
     shown: bool,
     entry: String,
     history: VecDeque<String>,
+    history_options: HistoryOptions,
+    unsaved_history: usize,
+    edits: BTreeMap<usize, String>,
     cursor: Option<usize>,
+    caret: usize,
+    anchor: Option<usize>,
+    word_boundary: WordBoundary,
+    undo: UndoStack,
+    last_edit: Option<EditKind>,
+    kill_ring: KillRing,
+    search: Option<Search>,
+    fuzzy_search: bool,
+    /// The prefix for `up_prefix`, and the history cursor when it was taken.
+    prefix: Option<(String, Option<usize>)>,
+    completion: Option<Completion>,
+    suggester: Suggester,
+    aliases: BTreeMap<String, String>,
+    output: Scrollback,
+    output_options: OutputOptions,
+    #[cfg(feature = "log")]
+    logger: Option<ConsoleLogger>,
+    #[cfg(feature = "tracing")]
+    trace_layer: Option<ConsoleLayer>,
+    #[cfg(feature = "winit")]
+    modifiers: winit::keyboard::ModifiersState,
 }
 
 #[derive(Default, Clone, PartialEq, Eq)]
@@ -125,26 +246,264 @@ impl Console {
     ///
     /// This uses the `FromStr` trait to parse the entry. You should implement this
     /// trait on the type you're using for your commands.
+    ///
+    /// If the entry starts with an alias, it is expanded before it is parsed.
+    /// If that fails because of an alias cycle, the error is added to the
+    /// output, and the entry is parsed as it was typed.
     pub fn confirm<Cmd: std::str::FromStr>(&mut self) -> Result<Cmd, Cmd::Err> {
-        let entry = self.entry();
-        let result = entry.parse();
+        self.take_expanded_or_report().parse()
+    }
+
+    /// The same as `confirm`, but if parsing fails the error is converted to an
+    /// `Error` and rendered against the entry with `Error::render`, ready to be
+    /// shown to the user.
+    pub fn confirm_rendered<Cmd>(&mut self) -> Result<Cmd, String>
+    where
+        Cmd: std::str::FromStr,
+        Cmd::Err: Into<Error>,
+    {
+        let entry = match self.take_expanded() {
+            (_, Ok(expanded)) => expanded,
+            (entry, Err(error)) => return Err(error.render(&entry)),
+        };
+        entry.parse().map_err(|e: Cmd::Err| e.into().render(&entry))
+    }
+
+    /// Splits the text entered so far into tokens, and clears the entry.
+    ///
+    /// Returns the raw entry, with any alias expanded, alongside the result of
+    /// `tokenize`, so that it's still available if tokenizing fails.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use dbgcmd::Console;
+    /// let mut console = Console::new();
+    /// console.set_entry("say 'hello there'".to_owned());
+    ///
+    /// let (raw, tokens) = console.confirm_tokens();
+    ///
+    /// if console.enabled() {
+    ///     assert_eq!(raw, "say 'hello there'");
+    ///     assert_eq!(tokens.unwrap()[1].text, "hello there");
+    /// }
+    /// ```
+    pub fn confirm_tokens(&mut self) -> (String, Result<Vec<Token>, TokenizeError>) {
+        let entry = self.take_expanded_or_report();
+        let tokens = tokenize(&entry);
+        (entry, tokens)
+    }
+
+    /// Runs the text entered so far as a command from `registry`, and clears
+    /// the entry.
+    ///
+    /// The entry is split into tokens, and the first token names the command
+    /// to run. See `Registry` for an example.
+    pub fn confirm_command<Ctx>(&mut self, registry: &mut Registry<Ctx>, ctx: &mut Ctx) -> Outcome {
+        match self.take_expanded() {
+            (_, Ok(entry)) => registry.dispatch(&entry, ctx),
+            (_, Err(error)) => Outcome::Failed(error),
+        }
+    }
+
+    /// The same as `confirm`, but entries that start with the name of a cvar
+    /// in `cvars` query or set it instead, and return `None`.
+    ///
+    /// The value of the cvar, or the rendered error if it could not be set, is
+    /// added to the output. See `Cvars` for an example.
+    pub fn confirm_with_cvars<Cmd: std::str::FromStr>(
+        &mut self,
+        cvars: &mut Cvars,
+    ) -> Option<Result<Cmd, Cmd::Err>> {
+        let entry = self.take_expanded_or_report();
+        match cvars.dispatch(&entry) {
+            Some(outcome) => {
+                self.print_outcome(&entry, outcome);
+                None
+            }
+            None => Some(entry.parse()),
+        }
+    }
+
+    /// Adds the output or error of running `entry` to the output.
+    fn print_outcome(&mut self, entry: &str, outcome: Outcome) {
+        match outcome {
+            Outcome::Empty => (),
+            Outcome::Output(output) => self.info(&output),
+            Outcome::Failed(error) => self.error(&error.render(entry)),
+        }
+    }
+
+    /// Takes the entry with `take_entry`, and expands any alias it starts
+    /// with. Returns the entry as it was typed, and the expanded entry.
+    fn take_expanded(&mut self) -> (String, Result<String, Error>) {
+        let entry = self.take_entry();
+        let expanded = alias::expand(&self.aliases, &entry);
+        (entry, expanded)
+    }
+
+    /// The same as `take_expanded`, but if expansion fails, the error is added
+    /// to the output and the entry is returned as it was typed.
+    fn take_expanded_or_report(&mut self) -> String {
+        match self.take_expanded() {
+            (_, Ok(expanded)) => expanded,
+            (entry, Err(error)) => {
+                self.error(&error.render(&entry));
+                entry
+            }
+        }
+    }
+
+    /// Defines an alias, replacing any existing alias with the same name.
+    ///
+    /// When a confirmed entry starts with `name`, it is replaced with
+    /// `expansion` before it is parsed. `$1` to `$9` in the expansion are
+    /// replaced with the arguments after the name, `$*` with all of them, and
+    /// `$$` with `$`. If the expansion doesn't use any arguments, they are
+    /// added to the end of it instead. Expansions can start with other aliases,
+    /// or with the alias's own name to wrap the command of the same name.
+    ///
+    /// The entry is stored in the history as it was typed, not expanded.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use dbgcmd::Console;
+    ///
+    /// let mut console = Console::new();
+    /// console.set_alias("gold", "give gold");
+    /// console.set_alias("tp", "teleport --x $1 --y $2");
+    ///
+    /// console.set_entry("gold 100".to_owned());
+    /// let gold = console.confirm::<String>();
+    ///
+    /// console.set_entry("tp 3 4".to_owned());
+    /// let tp = console.confirm::<String>();
+    ///
+    /// if console.enabled() {
+    ///     assert_eq!(gold.unwrap(), "give gold 100");
+    ///     assert_eq!(tp.unwrap(), "teleport --x 3 --y 4");
+    ///     assert_eq!(console.history().next(), Some("tp 3 4"));
+    /// }
+    /// ```
+    pub fn set_alias(&mut self, name: &str, expansion: &str) {
+        self.aliases.insert(name.to_owned(), expansion.to_owned());
+    }
+
+    /// Removes an alias. Returns `true` if it was defined.
+    pub fn remove_alias(&mut self, name: &str) -> bool {
+        self.aliases.remove(name).is_some()
+    }
+
+    /// Returns the expansion of an alias.
+    pub fn alias(&self, name: &str) -> Option<&str> {
+        self.aliases.get(name).map(String::as_str)
+    }
+
+    /// Returns an iterator over the names and expansions of all aliases, in
+    /// alphabetical order by name.
+    pub fn aliases(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.aliases
+            .iter()
+            .map(|(name, expansion)| (name.as_str(), expansion.as_str()))
+    }
+
+    /// Removes every alias.
+    pub fn clear_aliases(&mut self) {
+        self.aliases.clear();
+    }
+
+    /// Expands any alias that `entry` starts with, the same way as when it is
+    /// confirmed.
+    ///
+    /// Returns an error with the kind `ErrorKind::AliasCycle` if the aliases
+    /// expand into themselves.
+    pub fn expand_aliases(&self, entry: &str) -> Result<String, Error> {
+        alias::expand(&self.aliases, entry)
+    }
+
+    /// Saves the aliases to a file, so that they can be loaded again with
+    /// `load_aliases`.
+    ///
+    /// Each alias is written on its own line, as its name followed by its
+    /// expansion, escaped the same way as a history file. The file is replaced
+    /// atomically.
+    pub fn save_aliases<P: AsRef<std::path::Path>>(&self, path: P) -> std::io::Result<()> {
+        let lines: Vec<String> = self
+            .aliases()
+            .map(|(name, expansion)| format!("{} {}", name, expansion))
+            .collect();
+        lines::write_escaped(path.as_ref(), lines.iter().map(String::as_str))
+    }
 
-        self.history
-            .push_front(entry.to_owned());
+    /// Loads aliases from a file written by `save_aliases`. They are added to
+    /// the current aliases, replacing any with the same names.
+    ///
+    /// Blank lines and lines starting with `#` are ignored, as are lines with
+    /// a name but no expansion. If the file does not exist, this is treated as
+    /// an empty file.
+    pub fn load_aliases<P: AsRef<std::path::Path>>(&mut self, path: P) -> std::io::Result<()> {
+        for line in lines::read_escaped(path.as_ref())? {
+            let line = line.trim_start();
+            if line.starts_with('#') {
+                continue;
+            }
+            if let Some((name, expansion)) = line.split_once(char::is_whitespace) {
+                if !expansion.trim().is_empty() {
+                    self.set_alias(name, expansion);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Adds the text entered so far to the history, and clears the entry.
+    fn take_entry(&mut self) -> String {
+        let entry = self.entry().to_owned();
+        if self.output_options.echo {
+            self.print(Level::Echo, &entry);
+        }
+        self.output.scroll_to_bottom();
+
+        let previous = self.history.front().map(String::as_str);
+        if self.history_options.should_store(&entry, previous) {
+            self.history.push_front(entry.clone());
+            self.unsaved_history += 1;
+            self.truncate_history();
+        }
         self.entry.clear();
+        self.edits.clear();
         self.cursor = None;
-
-        result
+        self.caret = 0;
+        self.anchor = None;
+        self.search = None;
+        self.prefix = None;
+        self.completion = None;
+        self.reset_suggestion();
+        self.undo.clear();
+        self.last_edit = None;
+
+        entry
     }
 
     /// Returns a reference to the text entered so far.
+    ///
+    /// While browsing the history, this is the history item being viewed,
+    /// including any edits made to it since the last `confirm`.
     pub fn entry(&self) -> &str {
         match self.cursor {
-            Some(n) => &self.history[n],
+            Some(n) => self.edits.get(&n).unwrap_or(&self.history[n]),
             None => &self.entry,
         }
     }
 
+    /// Returns the text that was typed before browsing the history. This is
+    /// restored to the entry when scrolling back down past the newest history
+    /// item.
+    pub fn draft(&self) -> &str {
+        &self.entry
+    }
+
     /// Returns an iterator over the previously confirmed entries. This yields
     /// items in the order from most recent to least recent.
     pub fn history(&self) -> impl Iterator<Item = &str> {
@@ -156,37 +515,338 @@ impl Console {
         self.history.iter().map(String::as_ref).dedup()
     }
 
+    /// Returns the position of the caret within the entry, as a byte offset.
+    ///
+    /// This is always on a grapheme boundary, so `&entry()[..caret()]` is the
+    /// text before the caret.
+    pub fn caret(&self) -> usize {
+        self.caret
+    }
+
     /// Returns the number of items in the history. Note that this includes
     /// duplicate entries.
     pub fn history_len(&self) -> usize {
         self.history.len()
     }
 
+    /// Returns the options controlling which entries are stored in the history.
+    pub fn history_options(&self) -> HistoryOptions {
+        self.history_options
+    }
+
+    /// Sets the options controlling which entries are stored in the history.
+    ///
+    /// If the history is longer than the new `max_len`, the oldest entries are
+    /// evicted immediately.
+    pub fn set_history_options(&mut self, options: HistoryOptions) {
+        self.history_options = options;
+        self.truncate_history();
+    }
+
+    /// Evicts the oldest history entries beyond the configured maximum length.
+    fn truncate_history(&mut self) {
+        let max_len = match self.history_options.max_len {
+            Some(max_len) if max_len < self.history.len() => max_len,
+            _ => return,
+        };
+        if self.cursor.is_some_and(|n| n >= max_len) {
+            self.leave_history();
+        }
+        self.edits.split_off(&max_len);
+        self.history.truncate(max_len);
+        self.reset_suggestion();
+        self.unsaved_history = self.unsaved_history.min(max_len);
+        self.undo.clear();
+        self.break_edit();
+    }
+
+    /// Adds `text` to the end of the output. Each line of the text becomes its
+    /// own `OutputLine`.
+    ///
+    /// If the output is scrolled back, it stays scrolled to the same lines.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use dbgcmd::{Console, Level};
+    ///
+    /// let mut console = Console::new();
+    /// console.set_entry("status".to_owned());
+    /// console.confirm::<String>();
+    /// console.print(Level::Info, "health: 100\nammo: 12");
+    ///
+    /// if console.enabled() {
+    ///     let lines: Vec<_> = console.output().map(|line| line.text.as_str()).collect();
+    ///     assert_eq!(lines, vec!["status", "health: 100", "ammo: 12"]);
+    ///     assert_eq!(console.output().next().unwrap().level, Level::Echo);
+    /// }
+    /// ```
+    pub fn print(&mut self, level: Level, text: &str) {
+        self.output.push(level, text, self.output_options.max_lines);
+    }
+
+    /// Adds `text` to the output at `Level::Info`.
+    pub fn info(&mut self, text: &str) {
+        self.print(Level::Info, text);
+    }
+
+    /// Adds `text` to the output at `Level::Warn`.
+    pub fn warn(&mut self, text: &str) {
+        self.print(Level::Warn, text);
+    }
+
+    /// Adds `text` to the output at `Level::Error`.
+    pub fn error(&mut self, text: &str) {
+        self.print(Level::Error, text);
+    }
+
+    /// Returns an iterator over every line of output, from oldest to newest.
+    pub fn output(&self) -> impl DoubleEndedIterator<Item = &OutputLine> + ExactSizeIterator {
+        self.output.lines().iter()
+    }
+
+    /// Returns an iterator over the lines of output that are scrolled into
+    /// view, from oldest to newest. There are at most `output_height` of them.
+    pub fn visible_output(
+        &self,
+    ) -> impl DoubleEndedIterator<Item = &OutputLine> + ExactSizeIterator {
+        self.output.visible()
+    }
+
+    /// Returns the number of lines of output.
+    pub fn output_len(&self) -> usize {
+        self.output.lines().len()
+    }
+
+    /// Removes every line of output.
+    pub fn clear_output(&mut self) {
+        self.output.clear();
+    }
+
+    /// Returns the options controlling how the output is kept.
+    pub fn output_options(&self) -> OutputOptions {
+        self.output_options
+    }
+
+    /// Sets the options controlling how the output is kept.
+    ///
+    /// If there are more lines than the new `max_lines`, the oldest lines are
+    /// evicted immediately.
+    pub fn set_output_options(&mut self, options: OutputOptions) {
+        self.output_options = options;
+        self.output.truncate(options.max_lines);
+    }
+
+    /// Returns the number of lines of output that fit on screen, as set by
+    /// `set_output_height`.
+    pub fn output_height(&self) -> usize {
+        self.output.height()
+    }
+
+    /// Sets the number of lines of output that fit on screen. This is how far
+    /// `page_up` and `page_down` scroll, and how many lines `visible_output`
+    /// returns.
+    ///
+    /// This is `0` until it is set, which means every line is visible and the
+    /// output can't be scrolled.
+    pub fn set_output_height(&mut self, height: usize) {
+        self.output.set_height(height);
+    }
+
+    /// Returns how many lines the output is scrolled back from the newest
+    /// line. This is `0` when the newest line is in view.
+    pub fn output_scroll(&self) -> usize {
+        self.output.scroll()
+    }
+
+    /// Scrolls the output back towards older lines.
+    ///
+    /// Returns `false` if the oldest line was already in view.
+    pub fn scroll_up(&mut self, lines: usize) -> bool {
+        self.output.scroll_by(true, lines)
+    }
+
+    /// Scrolls the output forward towards newer lines.
+    ///
+    /// Returns `false` if the newest line was already in view.
+    pub fn scroll_down(&mut self, lines: usize) -> bool {
+        self.output.scroll_by(false, lines)
+    }
+
+    /// Scrolls the output back by one `output_height`. This is usually bound
+    /// to Page Up.
+    ///
+    /// Returns `false` if the oldest line was already in view.
+    pub fn page_up(&mut self) -> bool {
+        self.scroll_up(self.output.height())
+    }
+
+    /// Scrolls the output forward by one `output_height`. This is usually
+    /// bound to Page Down.
+    ///
+    /// Returns `false` if the newest line was already in view.
+    pub fn page_down(&mut self) -> bool {
+        self.scroll_down(self.output.height())
+    }
+
+    /// Scrolls the output so that the newest line is in view. This happens
+    /// automatically whenever an entry is confirmed.
+    pub fn scroll_to_bottom(&mut self) {
+        self.output.scroll_to_bottom();
+    }
+
+    /// Saves the history to a file, so that it can be loaded again with
+    /// `load_history`.
+    ///
+    /// Each entry is written on its own line, from oldest to newest, with
+    /// newlines and backslashes escaped. If the file already exists, only the
+    /// entries confirmed since the last save or load are added to the end of
+    /// it, so that several consoles can share one history file without losing
+    /// each other's entries. The file is replaced atomically.
+    ///
+    /// While saving, an advisory lock is held on a `.lock` file next to the
+    /// history file, so that consoles saving at the same time take turns.
+    pub fn save_history<P: AsRef<std::path::Path>>(&mut self, path: P) -> std::io::Result<()> {
+        let path = path.as_ref();
+        let _lock = lines::lock(path)?;
+        let mut entries = lines::read_escaped(path)?;
+        entries.extend(
+            self.history
+                .iter()
+                .take(self.unsaved_history)
+                .rev()
+                .cloned(),
+        );
+        if let Some(max_len) = self.history_options.max_len {
+            let excess = entries.len().saturating_sub(max_len);
+            entries.drain(..excess);
+        }
+
+        lines::write_escaped(path, entries.iter().map(String::as_str))?;
+        self.unsaved_history = 0;
+        Ok(())
+    }
+
+    /// Loads the history from a file written by `save_history`, replacing the
+    /// current history. Entries confirmed since the last save or load are kept,
+    /// as the newest entries.
+    ///
+    /// If the file does not exist, this is treated as an empty history.
+    pub fn load_history<P: AsRef<std::path::Path>>(&mut self, path: P) -> std::io::Result<()> {
+        let entries = lines::read_escaped(path.as_ref())?;
+        self.leave_history();
+        self.history.truncate(self.unsaved_history);
+        self.history.extend(entries.into_iter().rev());
+        self.reset_suggestion();
+        self.undo.clear();
+        self.break_edit();
+        self.truncate_history();
+        Ok(())
+    }
+
     /// Clears and sets the value of the entire command entry.
+    ///
+    /// The caret is moved to the end of the new entry.
     pub fn set_entry(&mut self, entry: String) {
+        self.begin_edit(EditKind::Other);
+        self.caret = entry.len();
         self.entry = entry;
         self.cursor = None;
+        self.update_suggestion();
     }
 
-    /// Receive an individual character and append it to the command entry.
-    pub fn receive_char(&mut self, ch: char) {
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            entry: self.entry.clone(),
+            edits: self.edits.clone(),
+            cursor: self.cursor,
+            caret: self.caret,
+        }
+    }
+
+    fn restore(&mut self, snapshot: Snapshot) {
+        self.entry = snapshot.entry;
+        self.edits = snapshot.edits;
+        self.cursor = snapshot.cursor;
+        self.caret = snapshot.caret;
+        self.anchor = None;
+        self.completion = None;
+        self.last_edit = None;
+        self.update_suggestion();
+    }
+
+    /// Records an undo step for an edit that is about to happen, unless it
+    /// can be merged with the previous one.
+    fn begin_edit(&mut self, kind: EditKind) {
+        self.end_search();
+        if kind != EditKind::Navigate {
+            self.prefix = None;
+        }
+        if !(kind.coalesces() && self.last_edit == Some(kind)) {
+            let snapshot = self.snapshot();
+            self.undo.push(snapshot);
+        }
+        self.anchor = None;
+        self.completion = None;
+        self.last_edit = Some(kind);
+    }
+
+    /// Marks the end of a run of edits that would otherwise be undone together.
+    fn break_edit(&mut self) {
+        self.last_edit = None;
+    }
+
+    /// Returns the entry for editing. If a history item is currently being
+    /// viewed, the edit is made to a copy of it so the history is untouched.
+    /// That copy is kept until the next `confirm`, so the edit is still there
+    /// after browsing away from the item and back again.
+    fn entry_mut(&mut self) -> &mut String {
+        match self.cursor {
+            Some(n) => {
+                let history = &self.history;
+                self.edits.entry(n).or_insert_with(|| history[n].clone())
+            }
+            None => &mut self.entry,
+        }
+    }
+
+    /// Stops browsing the history, making the history item being viewed the
+    /// new entry, and discards any other edits to history items. This is for
+    /// when the history itself changes, so the edits can no longer be trusted.
+    fn leave_history(&mut self) {
         if self.cursor.is_some() {
             self.entry = self.entry().to_owned();
             self.cursor = None;
         }
-        self.entry.push(ch)
+        self.edits.clear();
+        self.update_suggestion();
+    }
+
+    /// Receive an individual character and insert it into the command entry
+    /// at the caret.
+    ///
+    /// If any text is selected, it is replaced.
+    pub fn receive_char(&mut self, ch: char) {
+        self.receive_text(ch.encode_utf8(&mut [0; 4]));
     }
 
-    /// Receive text and append it to the command entry.
+    /// Receive text and insert it into the command entry at the caret.
+    ///
+    /// If any text is selected, it is replaced.
     pub fn receive_text(&mut self, text: &str) {
-        if self.cursor.is_some() {
-            self.entry = self.entry().to_owned();
-            self.cursor = None;
+        if let Some(search) = &mut self.search {
+            search.query.push_str(text);
+            let from = search.matched.unwrap_or(0);
+            self.find_search_match(|items, query, fuzzy| {
+                search::find_older(items, query, fuzzy, from)
+            });
+            return;
         }
-        self.entry.push_str(text)
+        let range = self.selection().unwrap_or(self.caret..self.caret);
+        self.replace_range(range, text, EditKind::Insert);
     }
 
-    /// Receive an individual character and append it to the command entry
+    /// Receive an individual character and insert it into the command entry
     /// if the `filter` argument returns true for it.
     ///
     /// Useful for limiting which characters can be entered.
@@ -211,7 +871,7 @@ impl Console {
         accept
     }
 
-    /// Receive text and append it to the command entry
+    /// Receive text and insert it into the command entry
     /// if the `filter` argument returns true for it.
     pub fn receive_text_if<F: Fn(&str) -> bool>(&mut self, text: &str, filter: F) -> bool {
         let accept = filter(text);
@@ -221,395 +881,2997 @@ impl Console {
         accept
     }
 
-    /// Removes the last character of the command entry.
-    pub fn backspace(&mut self) {
-        if self.cursor.is_some() {
-            self.entry = self.entry().to_owned();
-            self.cursor = None;
+    /// Replaces the given byte range of the entry with `text`, leaving the
+    /// caret after the inserted text.
+    fn replace_range(&mut self, range: Range<usize>, text: &str, kind: EditKind) {
+        if range.is_empty() && text.is_empty() {
+            return;
         }
-        self.entry.pop();
+        self.begin_edit(kind);
+        self.caret = range.start + text.len();
+        self.entry_mut().replace_range(range, text);
+        self.update_suggestion();
     }
 
-    /// Clears the command entry.
-    pub fn clear(&mut self) {
-        self.entry.clear();
+    /// Removes the given byte range from the entry, keeping the caret in place
+    /// relative to the surrounding text.
+    fn remove_range(&mut self, range: Range<usize>, kind: EditKind) {
+        if range.is_empty() {
+            return;
+        }
+        self.begin_edit(kind);
+        if self.caret >= range.end {
+            self.caret -= range.len();
+        } else if self.caret > range.start {
+            self.caret = range.start;
+        }
+        self.entry_mut().replace_range(range, "");
+        self.update_suggestion();
     }
 
-    /// Clears the entire command history.
-    pub fn clear_history(&mut self) {
-        self.history.clear();
+    /// Removes the character before the caret, or the selected text if there
+    /// is any.
+    pub fn backspace(&mut self) {
+        if let Some(search) = &mut self.search {
+            search.query.pop();
+            self.find_search_match(|items, query, fuzzy| {
+                search::find_older(items, query, fuzzy, 0)
+            });
+            return;
+        }
+        let range = match self.selection() {
+            Some(selection) => selection,
+            None => text::prev_grapheme(self.entry(), self.caret)..self.caret,
+        };
+        self.remove_range(range, EditKind::Other);
     }
 
-    /// Cycles through the command history towards older entries.
-    ///
-    /// Returns `true` if there was an older entry, and `false` if not. If there
-    /// is no older entry, the cursor does not move.
-    pub fn up(&mut self) -> bool {
-        let (cursor, moved) = match self.cursor {
-            None => (Some(0), true),
-            Some(n) if n < (self.history.len() - 1) => (Some(n + 1), true),
-            same => (same, false),
+    /// Removes the character after the caret, or the selected text if there
+    /// is any.
+    pub fn delete(&mut self) {
+        let range = match self.selection() {
+            Some(selection) => selection,
+            None => self.caret..text::next_grapheme(self.entry(), self.caret),
         };
-        self.cursor = cursor;
-        moved
+        self.remove_range(range, EditKind::Other);
     }
 
-    /// Cycles through the command history towards older entries, ignoring duplicates.
-    ///
-    /// Returns `true` if there was an older entry that differs from the current entry.
-    pub fn up_deduped(&mut self) -> bool {
-        let starting_entry = self.entry().to_owned();
-        while self.up() && self.entry() == starting_entry {}
-        starting_entry != self.entry()
+    /// Removes the given byte range and saves it to the kill ring. Consecutive
+    /// kills in the same direction are joined into a single kill.
+    fn kill_range(&mut self, range: Range<usize>, kind: EditKind) {
+        if range.is_empty() {
+            return;
+        }
+        let killed = self.entry()[range.clone()].to_owned();
+        match (self.last_edit, kind) {
+            (Some(EditKind::KillLeft), EditKind::KillLeft) => self.kill_ring.prepend(&killed),
+            (Some(EditKind::KillRight), EditKind::KillRight) => self.kill_ring.append(&killed),
+            _ => self.kill_ring.push(killed),
+        }
+        self.remove_range(range, kind);
     }
 
-    /// Cycles through the command history towards newer entries.
+    /// Removes the word before the caret, along with any separators between
+    /// it and the caret. This is usually bound to Ctrl+W.
     ///
-    /// Returns `true` if there was a newer entry, including the current input.
-    /// Returns `false` if there was no newer entry.
-    pub fn down(&mut self) -> bool {
-        let (cursor, moved) = match self.cursor {
-            Some(n) if n > 0 => (Some(n - 1), true),
-            prev => (None, prev.is_some()),
-        };
-        self.cursor = cursor;
-        moved
+    /// The removed text is saved to the kill ring.
+    pub fn delete_word_left(&mut self) {
+        let start = text::word_start(self.entry(), self.caret, self.word_boundary);
+        self.kill_range(start..self.caret, EditKind::KillLeft);
     }
 
-    /// Cycles through the command history towards newer entries, ignoring duplicates.
+    /// Removes the word after the caret, along with any separators between
+    /// the caret and it. This is usually bound to Alt+D.
     ///
-    /// Returns `true` if there was a newer entry that differs from the current entry.
-    pub fn down_deduped(&mut self) -> bool {
-        let starting_entry = self.entry().to_owned();
-        while self.down() && self.entry() == starting_entry {}
-        starting_entry != self.entry()
+    /// The removed text is saved to the kill ring.
+    pub fn delete_word_right(&mut self) {
+        let end = text::word_end(self.entry(), self.caret, self.word_boundary);
+        self.kill_range(self.caret..end, EditKind::KillRight);
     }
 
-    /// Whether or not the Console is in a visible state. This does not affect the
-    /// functionality of any other method, and is intended to be used by you to
-    /// decide whether or not to render the console.
-    pub fn shown(&self) -> bool {
-        self.shown
+    /// Removes everything before the caret. This is usually bound to Ctrl+U.
+    ///
+    /// The removed text is saved to the kill ring.
+    pub fn kill_to_start(&mut self) {
+        self.kill_range(0..self.caret, EditKind::KillLeft);
     }
 
-    /// Sets the Console to be visible.
-    pub fn show(&mut self) {
-        self.shown = true;
+    /// Removes everything after the caret. This is usually bound to Ctrl+K.
+    ///
+    /// The removed text is saved to the kill ring.
+    pub fn kill_to_end(&mut self) {
+        let end = self.entry().len();
+        self.kill_range(self.caret..end, EditKind::KillRight);
     }
 
-    /// Sets the Console to be hidden.
-    pub fn hide(&mut self) {
-        self.shown = false;
+    /// Inserts the most recently killed text at the caret. This is usually
+    /// bound to Ctrl+Y.
+    ///
+    /// Returns `true` if there was anything in the kill ring to insert.
+    pub fn yank(&mut self) -> bool {
+        let text = match self.kill_ring.yank() {
+            Some(text) => text.to_owned(),
+            None => return false,
+        };
+        let start = self.caret;
+        let end = start + text.len();
+        self.replace_range(start..start, &text, EditKind::Yank { start, end });
+        true
     }
 
-    /// Toggles the visible state of the Console.
-    pub fn toggle_shown(&mut self) {
-        self.shown = !self.shown;
+    /// Replaces the text inserted by the previous `yank` or `yank_pop` with
+    /// the next older kill from the kill ring. This is usually bound to Alt+Y.
+    ///
+    /// Returns `true` if the text was replaced. This does nothing unless the
+    /// previous edit was a yank.
+    pub fn yank_pop(&mut self) -> bool {
+        let (start, end) = match self.last_edit {
+            Some(EditKind::Yank { start, end }) => (start, end),
+            _ => return false,
+        };
+        let text = match self.kill_ring.rotate() {
+            Some(text) => text.to_owned(),
+            None => return false,
+        };
+        let kind = EditKind::Yank {
+            start,
+            end: start + text.len(),
+        };
+        self.replace_range(start..end, &text, kind);
+        true
     }
-}
 
-#[cfg(not(any(debug_assertions, feature = "force-enabled")))]
-impl Console {
-    pub const fn enabled(&self) -> bool {
-        false
+    /// Returns an iterator over the kill ring. This yields items in the order
+    /// from most recent to least recent.
+    pub fn kills(&self) -> impl Iterator<Item = &str> {
+        self.kill_ring.iter()
     }
 
-    pub fn confirm<Cmd: std::str::FromStr>(&mut self) -> Result<Cmd, Cmd::Err> {
-        "".parse()
+    /// Returns the maximum number of kills saved in the kill ring.
+    pub fn kill_ring_capacity(&self) -> usize {
+        self.kill_ring.capacity()
     }
 
-    pub fn entry(&self) -> &str {
-        ""
+    /// Sets the maximum number of kills saved in the kill ring. Older kills are
+    /// forgotten once this is exceeded. The default is 32.
+    pub fn set_kill_ring_capacity(&mut self, capacity: usize) {
+        self.kill_ring.set_capacity(capacity);
     }
 
-    pub fn history(&self) -> impl Iterator<Item = &str> {
-        std::iter::empty()
+    /// Clears the command entry.
+    pub fn clear(&mut self) {
+        if !self.entry().is_empty() {
+            self.begin_edit(EditKind::Other);
+        }
+        self.entry_mut().clear();
+        self.caret = 0;
+        self.update_suggestion();
     }
 
-    pub fn history_deduped(&self) -> impl Iterator<Item = &str> {
-        std::iter::empty()
+    /// Reverts the most recent edit to the entry.
+    ///
+    /// Returns `true` if there was an edit to undo.
+    pub fn undo(&mut self) -> bool {
+        let current = self.snapshot();
+        match self.undo.undo(current) {
+            Some(snapshot) => {
+                self.restore(snapshot);
+                true
+            }
+            None => false,
+        }
     }
 
-    pub fn history_len(&self) -> usize {
-        0
+    /// Reapplies the most recently undone edit to the entry.
+    ///
+    /// Returns `true` if there was an edit to redo.
+    pub fn redo(&mut self) -> bool {
+        let current = self.snapshot();
+        match self.undo.redo(current) {
+            Some(snapshot) => {
+                self.restore(snapshot);
+                true
+            }
+            None => false,
+        }
     }
 
-    pub fn shown(&self) -> bool {
-        false
+    /// Returns the maximum number of edits that can be undone.
+    pub fn undo_limit(&self) -> usize {
+        self.undo.limit()
     }
 
-    pub fn set_entry(&mut self, _entry: String) {}
+    /// Sets the maximum number of edits that can be undone. Older edits are
+    /// forgotten once this is exceeded. The default is 100.
+    ///
+    /// Consecutive inserted characters, consecutive history movements, and
+    /// consecutive completions are each undone together as a single edit.
+    pub fn set_undo_limit(&mut self, limit: usize) {
+        self.undo.set_limit(limit);
+    }
 
-    pub fn receive_char(&mut self, _ch: char) {}
+    /// Moves the caret to `caret`, either extending the selection or clearing
+    /// it. Returns `true` if the caret moved.
+    fn move_caret(&mut self, caret: usize, select: bool) -> bool {
+        self.end_search();
+        self.prefix = None;
+        self.completion = None;
+        if select {
+            self.anchor.get_or_insert(self.caret);
+        } else {
+            self.anchor = None;
+        }
+        let moved = caret != self.caret;
+        self.caret = caret;
+        self.break_edit();
+        moved
+    }
 
-    pub fn receive_char_if<F: Fn(char) -> bool>(&mut self, _ch: char, _filter: F) -> bool {
-        false
+    /// Moves the caret one character to the left. If there is a selection,
+    /// the caret moves to its start instead and the selection is cleared.
+    ///
+    /// Returns `true` if the caret moved, and `false` if it was already at
+    /// the start of the entry.
+    pub fn left(&mut self) -> bool {
+        match self.selection() {
+            Some(selection) => {
+                self.move_caret(selection.start, false);
+                true
+            }
+            None => {
+                let caret = text::prev_grapheme(self.entry(), self.caret);
+                self.move_caret(caret, false)
+            }
+        }
     }
 
-    pub fn backspace(&mut self) {}
-    pub fn clear(&mut self) {}
-    pub fn clear_history(&mut self) {}
-    pub fn up(&mut self) -> bool {
-        false
+    /// Moves the caret one character to the right. If there is a selection,
+    /// the caret moves to its end instead and the selection is cleared.
+    ///
+    /// Returns `true` if the caret moved, and `false` if it was already at
+    /// the end of the entry.
+    pub fn right(&mut self) -> bool {
+        match self.selection() {
+            Some(selection) => {
+                self.move_caret(selection.end, false);
+                true
+            }
+            None => {
+                let caret = text::next_grapheme(self.entry(), self.caret);
+                self.move_caret(caret, false)
+            }
+        }
     }
-    pub fn down(&mut self) -> bool {
-        false
+
+    /// Moves the caret to the start of the previous word.
+    ///
+    /// Returns `true` if the caret moved.
+    pub fn word_left(&mut self) -> bool {
+        let caret = text::word_start(self.entry(), self.caret, self.word_boundary);
+        self.move_caret(caret, false)
     }
-    pub fn up_deduped(&mut self) -> bool {
-        false
+
+    /// Moves the caret to the end of the next word.
+    ///
+    /// Returns `true` if the caret moved.
+    pub fn word_right(&mut self) -> bool {
+        let caret = text::word_end(self.entry(), self.caret, self.word_boundary);
+        self.move_caret(caret, false)
     }
-    pub fn down_deduped(&mut self) -> bool {
-        false
+
+    /// Returns how word-wise movements and deletions decide where words begin
+    /// and end.
+    pub fn word_boundary(&self) -> WordBoundary {
+        self.word_boundary
     }
-    pub fn show(&mut self) {}
-    pub fn hide(&mut self) {}
-    pub fn toggle_shown(&mut self) {}
-}
 
-#[cfg(all(feature = "winit", any(debug_assertions, feature = "force-enabled")))]
-impl Console {
-    pub fn handle_winit_event(&mut self, event: &winit::event::Event<()>) {
-        use winit::{
-            event::{ElementState, Event, WindowEvent},
-            keyboard::{Key, NamedKey},
+    /// Sets how word-wise movements and deletions decide where words begin
+    /// and end. The default is `WordBoundary::Whitespace`.
+    pub fn set_word_boundary(&mut self, boundary: WordBoundary) {
+        self.word_boundary = boundary;
+    }
+
+    /// Moves the caret to the start of the entry.
+    ///
+    /// Returns `true` if the caret moved.
+    pub fn home(&mut self) -> bool {
+        self.move_caret(0, false)
+    }
+
+    /// Moves the caret to the end of the entry.
+    ///
+    /// Returns `true` if the caret moved.
+    pub fn end(&mut self) -> bool {
+        let len = self.entry().len();
+        self.move_caret(len, false)
+    }
+
+    /// Returns the selected range of the entry, as byte offsets, if any text
+    /// is selected. The caret is always at one end of this range.
+    pub fn selection(&self) -> Option<Range<usize>> {
+        let anchor = self.anchor?;
+        match anchor.cmp(&self.caret) {
+            std::cmp::Ordering::Less => Some(anchor..self.caret),
+            std::cmp::Ordering::Equal => None,
+            std::cmp::Ordering::Greater => Some(self.caret..anchor),
+        }
+    }
+
+    /// Returns the selected text, if any.
+    pub fn selected_text(&self) -> Option<&str> {
+        self.selection().map(|selection| &self.entry()[selection])
+    }
+
+    /// Moves the caret one character to the left, extending the selection.
+    ///
+    /// Returns `true` if the caret moved.
+    pub fn select_left(&mut self) -> bool {
+        let caret = text::prev_grapheme(self.entry(), self.caret);
+        self.move_caret(caret, true)
+    }
+
+    /// Moves the caret one character to the right, extending the selection.
+    ///
+    /// Returns `true` if the caret moved.
+    pub fn select_right(&mut self) -> bool {
+        let caret = text::next_grapheme(self.entry(), self.caret);
+        self.move_caret(caret, true)
+    }
+
+    /// Moves the caret to the start of the previous word, extending the
+    /// selection.
+    ///
+    /// Returns `true` if the caret moved.
+    pub fn select_word_left(&mut self) -> bool {
+        let caret = text::word_start(self.entry(), self.caret, self.word_boundary);
+        self.move_caret(caret, true)
+    }
+
+    /// Moves the caret to the end of the next word, extending the selection.
+    ///
+    /// Returns `true` if the caret moved.
+    pub fn select_word_right(&mut self) -> bool {
+        let caret = text::word_end(self.entry(), self.caret, self.word_boundary);
+        self.move_caret(caret, true)
+    }
+
+    /// Moves the caret to the start of the entry, extending the selection.
+    ///
+    /// Returns `true` if the caret moved.
+    pub fn select_home(&mut self) -> bool {
+        self.move_caret(0, true)
+    }
+
+    /// Moves the caret to the end of the entry, extending the selection.
+    ///
+    /// Returns `true` if the caret moved.
+    pub fn select_end(&mut self) -> bool {
+        let len = self.entry().len();
+        self.move_caret(len, true)
+    }
+
+    /// Selects the entire entry, leaving the caret at the end.
+    pub fn select_all(&mut self) {
+        self.anchor = None;
+        self.move_caret(0, false);
+        self.select_end();
+    }
+
+    /// Clears the selection without moving the caret.
+    pub fn deselect(&mut self) {
+        self.anchor = None;
+    }
+
+    /// Replaces the selected text with `text`.
+    ///
+    /// Returns `true` if there was a selection to replace.
+    pub fn replace_selection(&mut self, text: &str) -> bool {
+        match self.selection() {
+            Some(selection) => {
+                self.replace_range(selection, text, EditKind::Other);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Copies the selected text to the given clipboard.
+    ///
+    /// Returns `true` if there was a selection to copy.
+    pub fn copy<C: Clipboard>(&self, clipboard: &mut C) -> bool {
+        match self.selected_text() {
+            Some(text) => {
+                clipboard.set(text.to_owned());
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Copies the selected text to the given clipboard, and removes it from
+    /// the entry.
+    ///
+    /// Returns `true` if there was a selection to cut.
+    pub fn cut<C: Clipboard>(&mut self, clipboard: &mut C) -> bool {
+        let copied = self.copy(clipboard);
+        if copied {
+            self.replace_selection("");
+        }
+        copied
+    }
+
+    /// Inserts the contents of the given clipboard at the caret, replacing the
+    /// selected text if there is any.
+    ///
+    /// Returns `true` if the clipboard had any text to paste.
+    pub fn paste<C: Clipboard>(&mut self, clipboard: &mut C) -> bool {
+        match clipboard.get() {
+            Some(text) => {
+                let range = self.selection().unwrap_or(self.caret..self.caret);
+                self.replace_range(range, &text, EditKind::Other);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Completes the token before the caret using `completer`. This is usually
+    /// bound to Tab.
+    ///
+    /// If there is only one candidate, it replaces the token, followed by a
+    /// space unless it ends in `/`. If there are several, the token is extended
+    /// to their longest common prefix, and they are listed by `completions`.
+    /// Calling this again, with no other edits in between, cycles through
+    /// them.
+    ///
+    /// Returns `false` if there was nothing to complete.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use dbgcmd::{Console, DefaultCompleter};
+    ///
+    /// let mut console = Console::new();
+    /// let mut completer = DefaultCompleter::new(vec!["spawn", "speed", "gravity"]);
+    ///
+    /// console.set_entry("g".to_owned());
+    /// console.complete(&mut completer);
+    ///
+    /// console.set_entry("sp".to_owned());
+    /// console.complete(&mut completer);
+    ///
+    /// if console.enabled() {
+    ///     assert_eq!(console.completions(), ["spawn", "speed"]);
+    ///     assert_eq!(console.completion_index(), None);
+    ///
+    ///     console.complete(&mut completer);
+    ///     assert_eq!(console.entry(), "spawn");
+    ///     assert_eq!(console.completion_index(), Some(0));
+    /// }
+    /// ```
+    pub fn complete<C: Completer + ?Sized>(&mut self, completer: &mut C) -> bool {
+        if let Some(completion) = &self.completion {
+            let selected = completion
+                .selected
+                .map_or(0, |i| (i + 1) % completion.candidates.len());
+            return self.select_completion(selected);
+        }
+
+        let context = CompletionContext::new(&self.entry()[..self.caret], &self.history);
+        let (span, partial) = (context.span(), context.partial().to_owned());
+        let mut candidates: Vec<String> = Vec::new();
+        for candidate in completer.complete(&context) {
+            if !candidates.contains(&candidate) {
+                candidates.push(candidate);
+            }
+        }
+
+        match candidates.len() {
+            0 => false,
+            1 => {
+                let mut text = tokenize::escape(&candidates[0]);
+                if !text.ends_with('/') {
+                    text.push(' ');
+                }
+                self.replace_range(span, &text, EditKind::Complete);
+                self.break_edit();
+                true
+            }
+            _ => {
+                let prefix = complete::common_prefix(&candidates);
+                if prefix.len() > partial.len() && prefix.starts_with(&partial) {
+                    let text = tokenize::escape(prefix);
+                    self.replace_range(span.clone(), &text, EditKind::Complete);
+                }
+                self.completion = Some(Completion {
+                    start: span.start,
+                    candidates,
+                    selected: None,
+                });
+                true
+            }
+        }
+    }
+
+    /// Returns the candidates from the last call to `complete`, if it found
+    /// more than one and nothing else has been edited since.
+    pub fn completions(&self) -> &[String] {
+        match &self.completion {
+            Some(completion) => &completion.candidates,
+            None => &[],
+        }
+    }
+
+    /// Returns the index in `completions` of the candidate currently in the
+    /// entry, if any.
+    pub fn completion_index(&self) -> Option<usize> {
+        self.completion.as_ref()?.selected
+    }
+
+    /// Puts the candidate at `index` in `completions` into the entry, as if
+    /// `complete` had cycled to it. This is useful for choosing a candidate
+    /// from a popup.
+    ///
+    /// Returns `false` if there is no such candidate.
+    pub fn select_completion(&mut self, index: usize) -> bool {
+        let completion = match self.completion.take() {
+            Some(completion) if index < completion.candidates.len() => completion,
+            completion => {
+                self.completion = completion;
+                return false;
+            }
         };
+        let text = tokenize::escape(&completion.candidates[index]);
+        self.replace_range(completion.start..self.caret, &text, EditKind::Complete);
+        self.completion = Some(Completion {
+            selected: Some(index),
+            ..completion
+        });
+        true
+    }
 
-        const VALID_CHARS: &str =
-            "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789 ._-\"'/\\~";
+    /// Stops completing, keeping the entry as it is.
+    pub fn cancel_completion(&mut self) {
+        self.completion = None;
+        self.break_edit();
+    }
 
-        if self.shown() {
-            match event {
-                Event::WindowEvent { event, .. } => match event {
-                    WindowEvent::KeyboardInput { event, .. } => {
-                        if event.state == ElementState::Pressed {
-                            match event.logical_key {
-                                Key::Named(NamedKey::Backspace) => {
-                                    self.backspace();
-                                }
-                                Key::Named(NamedKey::ArrowUp) => {
-                                    self.up_deduped();
-                                }
-                                Key::Named(NamedKey::ArrowDown) => {
-                                    self.down_deduped();
-                                }
-                                _ => {
-                                    if let Some(text) = &event.text {
-                                        let text = text.as_ref();
-                                        if VALID_CHARS.contains(text) {
-                                            self.receive_text(text);
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
-                    _ => (),
-                },
-                _ => (),
+    /// Returns the rest of the most recent history entry that starts with the
+    /// entry, for drawing after the caret as a suggestion. For example, if the
+    /// entry is `sp` and `spawn orc` is in the history, this is `awn orc`.
+    ///
+    /// There is only a suggestion while the caret is at the end of the entry,
+    /// and not while browsing or searching the history.
+    pub fn suggestion(&self) -> Option<&str> {
+        if self.cursor.is_some() || self.search.is_some() || self.caret != self.entry.len() {
+            return None;
+        }
+        let found = match self.suggester.get(&self.entry) {
+            Some(found) => found,
+            None => suggest::find(&self.history, &self.entry, 0),
+        };
+        found.map(|n| &self.history[n][self.entry.len()..])
+    }
+
+    /// Adds the whole of the current `suggestion` to the entry.
+    ///
+    /// Returns `false` if there was no suggestion.
+    pub fn accept_suggestion(&mut self) -> bool {
+        match self.suggestion() {
+            Some(suggestion) => {
+                let (end, suggestion) = (self.entry.len(), suggestion.to_owned());
+                self.replace_range(end..end, &suggestion, EditKind::Other);
+                true
             }
+            None => false,
         }
     }
-}
 
-#[cfg(all(
-    feature = "winit",
-    not(any(debug_assertions, feature = "force-enabled"))
-))]
-impl Console {
-    pub fn handle_winit_event(&mut self, _event: &winit::event::Event<()>) {}
-}
+    /// Adds the next word of the current `suggestion` to the entry, including
+    /// any punctuation or whitespace before it.
+    ///
+    /// Returns `false` if there was no suggestion.
+    pub fn accept_suggestion_word(&mut self) -> bool {
+        match self.suggestion() {
+            Some(suggestion) => {
+                let end = self.entry.len();
+                let full = format!("{}{}", self.entry, suggestion);
+                let word_end = text::word_end(&full, end, self.word_boundary);
+                self.replace_range(end..end, &full[end..word_end], EditKind::Other);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Brings the suggestion up to date after an edit to the draft.
+    fn update_suggestion(&mut self) {
+        if self.cursor.is_none() {
+            self.suggester.update(&self.history, &self.entry);
+        }
+    }
+
+    /// Forgets every suggestion and finds the one for the draft again. This is
+    /// for when the history changes.
+    fn reset_suggestion(&mut self) {
+        self.suggester.clear();
+        self.update_suggestion();
+    }
+
+    /// Starts a reverse incremental search through the history. This is
+    /// usually bound to Ctrl+R.
+    ///
+    /// While searching, `receive_char`, `receive_text`, and `backspace` edit
+    /// the search query instead of the entry, and the entry shows the most
+    /// recent history item containing the query. Any other edit or caret
+    /// movement accepts the search first.
+    pub fn start_search(&mut self) {
+        if self.search.is_none() {
+            self.search = Some(Search {
+                query: String::new(),
+                matched: None,
+                saved: self.snapshot(),
+            });
+            self.anchor = None;
+        }
+    }
+
+    /// Whether a history search is in progress.
+    pub fn searching(&self) -> bool {
+        self.search.is_some()
+    }
+
+    /// Returns the query of the history search in progress.
+    pub fn search_query(&self) -> Option<&str> {
+        self.search.as_ref().map(|search| search.query.as_str())
+    }
+
+    /// Returns the index into `history` of the current search match. This is
+    /// `None` if there is no search in progress, or nothing matches the query.
+    pub fn search_match(&self) -> Option<usize> {
+        self.search.as_ref().and_then(|search| search.matched)
+    }
+
+    /// Returns the range of the entry that matches the search query, as byte
+    /// offsets, for highlighting.
+    ///
+    /// For a fuzzy search, this runs from the first matched character to the
+    /// last. Use `search_match_indices` to highlight each of them.
+    pub fn search_match_range(&self) -> Option<Range<usize>> {
+        let search = self.search.as_ref()?;
+        search.matched?;
+        let entry = self.entry();
+        if !self.fuzzy_search {
+            let start = entry.find(&search.query)?;
+            return Some(start..start + search.query.len());
+        }
+        let indices = fuzzy_match(&search.query, entry)?.indices;
+        let mut offsets = entry
+            .char_indices()
+            .map(|(offset, ch)| offset..offset + ch.len_utf8());
+        let first = offsets.nth(*indices.first()?)?;
+        let last = match indices.len() {
+            1 => first.clone(),
+            len => offsets.nth(indices[len - 1] - indices[0] - 1)?,
+        };
+        Some(first.start..last.end)
+    }
+
+    /// Returns the indices of the characters in the entry that match the search
+    /// query, counted in `char`s, for highlighting.
+    pub fn search_match_indices(&self) -> Vec<usize> {
+        let search = match &self.search {
+            Some(search) if search.matched.is_some() => search,
+            _ => return Vec::new(),
+        };
+        let entry = self.entry();
+        if self.fuzzy_search {
+            return fuzzy_match(&search.query, entry).map_or(Vec::new(), |m| m.indices);
+        }
+        match entry.find(&search.query) {
+            Some(start) => {
+                let first = entry[..start].chars().count();
+                (first..first + search.query.chars().count()).collect()
+            }
+            None => Vec::new(),
+        }
+    }
+
+    /// Whether history search matches entries fuzzily, as described by
+    /// `fuzzy_match`, rather than by substring. This is off by default.
+    pub fn fuzzy_search(&self) -> bool {
+        self.fuzzy_search
+    }
+
+    /// Sets whether history search matches entries fuzzily, as described by
+    /// `fuzzy_match`, rather than by substring.
+    pub fn set_fuzzy_search(&mut self, fuzzy: bool) {
+        self.fuzzy_search = fuzzy;
+    }
+
+    /// Moves the search to the next older history item matching the query.
+    /// This is usually bound to Ctrl+R while searching.
+    ///
+    /// Returns `true` if there was an older match.
+    pub fn search_older(&mut self) -> bool {
+        let from = match &self.search {
+            Some(search) => search.matched.map_or(0, |n| n + 1),
+            None => return false,
+        };
+        self.step_search(|items, query, fuzzy| search::find_older(items, query, fuzzy, from))
+    }
+
+    /// Moves the search to the next newer history item matching the query.
+    /// This is usually bound to Ctrl+S while searching.
+    ///
+    /// Returns `true` if there was a newer match.
+    pub fn search_newer(&mut self) -> bool {
+        let before = match &self.search {
+            Some(search) => search.matched.unwrap_or(0),
+            None => return false,
+        };
+        self.step_search(|items, query, fuzzy| search::find_newer(items, query, fuzzy, before))
+    }
+
+    /// Ends the search, leaving the matched history item in the entry.
+    pub fn accept_search(&mut self) {
+        if self.search.is_some() {
+            self.end_search();
+            self.caret = self.entry().len();
+        }
+    }
+
+    /// Ends the search, restoring the entry to how it was before the search
+    /// started.
+    pub fn cancel_search(&mut self) {
+        if let Some(search) = self.search.take() {
+            self.restore(search.saved);
+        }
+    }
+
+    /// Ends the search in progress, if any, keeping the current match. The
+    /// search as a whole can then be undone in one step.
+    fn end_search(&mut self) {
+        if let Some(search) = self.search.take() {
+            if search.saved != self.snapshot() {
+                self.undo.push(search.saved);
+                self.last_edit = None;
+            }
+        }
+    }
+
+    /// Searches for a new match for the query, keeping the current match if
+    /// there is none. Returns `true` if a match was found.
+    fn step_search<F>(&mut self, find: F) -> bool
+    where
+        F: FnOnce(search::Items, &str, bool) -> Option<usize>,
+    {
+        let search = match &self.search {
+            Some(search) => search,
+            None => return false,
+        };
+        match find(self.search_items(), &search.query, self.fuzzy_search) {
+            Some(n) => {
+                self.show_search_match(Some(n));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Searches for a match for a changed query. If nothing matches, the entry
+    /// keeps showing the previous match, but `search_match` returns `None`.
+    fn find_search_match<F>(&mut self, find: F)
+    where
+        F: FnOnce(search::Items, &str, bool) -> Option<usize>,
+    {
+        if let Some(search) = &self.search {
+            let matched = find(self.search_items(), &search.query, self.fuzzy_search);
+            self.show_search_match(matched);
+        }
+    }
+
+    /// The history items to search, as they are shown in the entry.
+    fn search_items(&self) -> search::Items<'_> {
+        search::Items {
+            history: &self.history,
+            edits: &self.edits,
+        }
+    }
+
+    fn show_search_match(&mut self, matched: Option<usize>) {
+        if let Some(search) = &mut self.search {
+            search.matched = matched;
+        }
+        if let Some(n) = matched {
+            self.cursor = Some(n);
+        }
+        if let Some(range) = self.search_match_range() {
+            self.caret = range.start;
+        }
+    }
+
+    /// Clears the entire command history.
+    pub fn clear_history(&mut self) {
+        self.leave_history();
+        self.search = None;
+        self.prefix = None;
+        self.history.clear();
+        self.reset_suggestion();
+        self.unsaved_history = 0;
+        self.undo.clear();
+        self.last_edit = None;
+    }
+
+    /// Cycles through the command history towards older entries.
+    ///
+    /// Returns `true` if there was an older entry, and `false` if not. If there
+    /// is no older entry, the cursor does not move.
+    pub fn up(&mut self) -> bool {
+        let (cursor, moved) = match self.cursor {
+            None if !self.history.is_empty() => (Some(0), true),
+            Some(n) if n + 1 < self.history.len() => (Some(n + 1), true),
+            same => (same, false),
+        };
+        if moved {
+            self.show_history(cursor);
+        }
+        self.prefix = None;
+        moved
+    }
+
+    /// Cycles through the command history towards older entries, ignoring duplicates.
+    ///
+    /// Returns `true` if there was an older entry that differs from the current entry.
+    pub fn up_deduped(&mut self) -> bool {
+        let starting_entry = self.entry().to_owned();
+        while self.up() && self.entry() == starting_entry {}
+        starting_entry != self.entry()
+    }
+
+    /// Cycles through the command history towards newer entries.
+    ///
+    /// Returns `true` if there was a newer entry, including the current input.
+    /// Returns `false` if there was no newer entry.
+    pub fn down(&mut self) -> bool {
+        let (cursor, moved) = match self.cursor {
+            Some(n) if n > 0 => (Some(n - 1), true),
+            prev => (None, prev.is_some()),
+        };
+        if moved {
+            self.show_history(cursor);
+        }
+        self.prefix = None;
+        moved
+    }
+
+    /// Cycles through the command history towards newer entries, ignoring duplicates.
+    ///
+    /// Returns `true` if there was a newer entry that differs from the current entry.
+    pub fn down_deduped(&mut self) -> bool {
+        let starting_entry = self.entry().to_owned();
+        while self.down() && self.entry() == starting_entry {}
+        starting_entry != self.entry()
+    }
+
+    /// Cycles through the command history towards older entries, only visiting
+    /// entries that start with the text typed before navigation began. Entries
+    /// identical to the current one are skipped.
+    ///
+    /// The prefix is remembered across successive calls to `up_prefix` and
+    /// `down_prefix`, and is forgotten once the entry is edited, the caret is
+    /// moved, or the history is navigated some other way.
+    ///
+    /// Returns `true` if there was an older matching entry.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use dbgcmd::Console;
+    /// let mut console = Console::new();
+    ///
+    /// for entry in ["spawn orc", "heal", "spawn elf"] {
+    ///     console.set_entry(entry.to_owned());
+    ///     console.confirm::<String>();
+    /// }
+    ///
+    /// console.receive_text("sp");
+    ///
+    /// if console.enabled() {
+    ///     console.up_prefix();
+    ///     assert_eq!(console.entry(), "spawn elf");
+    ///
+    ///     console.up_prefix();
+    ///     assert_eq!(console.entry(), "spawn orc");
+    ///
+    ///     console.down_prefix();
+    ///     console.down_prefix();
+    ///     assert_eq!(console.entry(), "sp");
+    /// }
+    /// ```
+    pub fn up_prefix(&mut self) -> bool {
+        if self.prefix.is_none() {
+            self.prefix = Some((self.entry().to_owned(), self.cursor));
+        }
+        let start = self.cursor.map_or(0, |n| n + 1);
+        match self.find_prefixed(start..self.history.len()) {
+            Some(n) => {
+                self.show_history(Some(n));
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Cycles through the command history towards newer entries, only visiting
+    /// entries that start with the text typed before navigation began. Moving
+    /// past the newest matching entry returns to the entry that was shown when
+    /// navigation began.
+    ///
+    /// Returns `true` if there was a newer matching entry, including the one
+    /// navigation began from.
+    pub fn down_prefix(&mut self) -> bool {
+        let (n, start) = match (self.cursor, &self.prefix) {
+            (Some(n), Some((_, start))) => (n, *start),
+            _ => return false,
+        };
+        let newest = start.map_or(0, |start| start + 1);
+        if n < newest {
+            return false;
+        }
+        match self.find_prefixed((newest..n).rev()) {
+            Some(n) => self.show_history(Some(n)),
+            None => {
+                self.prefix = None;
+                self.show_history(start);
+            }
+        }
+        true
+    }
+
+    /// Returns the first history index in `indices` whose entry starts with the
+    /// remembered prefix, and differs from the current entry.
+    fn find_prefixed<I: Iterator<Item = usize>>(&self, mut indices: I) -> Option<usize> {
+        let prefix = self.prefix.as_ref()?.0.as_str();
+        let current = self.entry();
+        indices.find(|&i| self.history[i].starts_with(prefix) && self.history[i] != current)
+    }
+
+    /// Shows the history item at `cursor` in the entry, or the typed entry if
+    /// `cursor` is `None`, with the caret at the end.
+    fn show_history(&mut self, cursor: Option<usize>) {
+        self.begin_edit(EditKind::Navigate);
+        self.cursor = cursor;
+        self.caret = self.entry().len();
+        self.update_suggestion();
+    }
+
+    /// Whether or not the Console is in a visible state. This does not affect the
+    /// functionality of any other method, and is intended to be used by you to
+    /// decide whether or not to render the console.
+    pub fn shown(&self) -> bool {
+        self.shown
+    }
+
+    /// Sets the Console to be visible.
+    pub fn show(&mut self) {
+        self.shown = true;
+    }
+
+    /// Sets the Console to be hidden.
+    pub fn hide(&mut self) {
+        self.shown = false;
+    }
+
+    /// Toggles the visible state of the Console.
+    pub fn toggle_shown(&mut self) {
+        self.shown = !self.shown;
+    }
+}
+
+#[cfg(not(any(debug_assertions, feature = "force-enabled")))]
+impl Console {
+    pub const fn enabled(&self) -> bool {
+        false
+    }
+
+    pub fn confirm<Cmd: std::str::FromStr>(&mut self) -> Result<Cmd, Cmd::Err> {
+        "".parse()
+    }
+
+    pub fn confirm_rendered<Cmd>(&mut self) -> Result<Cmd, String>
+    where
+        Cmd: std::str::FromStr,
+        Cmd::Err: Into<Error>,
+    {
+        "".parse().map_err(|e: Cmd::Err| e.into().render(""))
+    }
+
+    pub fn confirm_tokens(&mut self) -> (String, Result<Vec<Token>, TokenizeError>) {
+        (String::new(), Ok(Vec::new()))
+    }
+
+    pub fn confirm_command<Ctx>(
+        &mut self,
+        _registry: &mut Registry<Ctx>,
+        _ctx: &mut Ctx,
+    ) -> Outcome {
+        Outcome::Empty
+    }
+
+    pub fn confirm_with_cvars<Cmd: std::str::FromStr>(
+        &mut self,
+        _cvars: &mut Cvars,
+    ) -> Option<Result<Cmd, Cmd::Err>> {
+        Some("".parse())
+    }
+
+    pub fn set_alias(&mut self, _name: &str, _expansion: &str) {}
+
+    pub fn remove_alias(&mut self, _name: &str) -> bool {
+        false
+    }
+
+    pub fn alias(&self, _name: &str) -> Option<&str> {
+        None
+    }
+
+    pub fn aliases(&self) -> impl Iterator<Item = (&str, &str)> {
+        std::iter::empty()
+    }
+
+    pub fn clear_aliases(&mut self) {}
+
+    pub fn expand_aliases(&self, entry: &str) -> Result<String, Error> {
+        Ok(entry.to_owned())
+    }
+
+    pub fn save_aliases<P: AsRef<std::path::Path>>(&self, _path: P) -> std::io::Result<()> {
+        Ok(())
+    }
+
+    pub fn load_aliases<P: AsRef<std::path::Path>>(&mut self, _path: P) -> std::io::Result<()> {
+        Ok(())
+    }
+
+    pub fn entry(&self) -> &str {
+        ""
+    }
+
+    pub fn history(&self) -> impl Iterator<Item = &str> {
+        std::iter::empty()
+    }
+
+    pub fn draft(&self) -> &str {
+        ""
+    }
+
+    pub fn history_deduped(&self) -> impl Iterator<Item = &str> {
+        std::iter::empty()
+    }
+
+    pub fn history_len(&self) -> usize {
+        0
+    }
+
+    pub fn history_options(&self) -> HistoryOptions {
+        HistoryOptions::default()
+    }
+
+    pub fn set_history_options(&mut self, _options: HistoryOptions) {}
+
+    pub fn print(&mut self, _level: Level, _text: &str) {}
+
+    pub fn info(&mut self, _text: &str) {}
+
+    pub fn warn(&mut self, _text: &str) {}
+
+    pub fn error(&mut self, _text: &str) {}
+
+    pub fn output(&self) -> impl DoubleEndedIterator<Item = &OutputLine> + ExactSizeIterator {
+        std::iter::empty()
+    }
+
+    pub fn visible_output(
+        &self,
+    ) -> impl DoubleEndedIterator<Item = &OutputLine> + ExactSizeIterator {
+        std::iter::empty()
+    }
+
+    pub fn output_len(&self) -> usize {
+        0
+    }
+
+    pub fn clear_output(&mut self) {}
+
+    pub fn output_options(&self) -> OutputOptions {
+        OutputOptions::default()
+    }
+
+    pub fn set_output_options(&mut self, _options: OutputOptions) {}
+
+    pub fn output_height(&self) -> usize {
+        0
+    }
+
+    pub fn set_output_height(&mut self, _height: usize) {}
+
+    pub fn output_scroll(&self) -> usize {
+        0
+    }
+
+    pub fn scroll_up(&mut self, _lines: usize) -> bool {
+        false
+    }
+
+    pub fn scroll_down(&mut self, _lines: usize) -> bool {
+        false
+    }
+
+    pub fn page_up(&mut self) -> bool {
+        false
+    }
+
+    pub fn page_down(&mut self) -> bool {
+        false
+    }
+
+    pub fn scroll_to_bottom(&mut self) {}
+
+    pub fn suggestion(&self) -> Option<&str> {
+        None
+    }
+
+    pub fn accept_suggestion(&mut self) -> bool {
+        false
+    }
+
+    pub fn accept_suggestion_word(&mut self) -> bool {
+        false
+    }
+
+    pub fn start_search(&mut self) {}
+
+    pub fn searching(&self) -> bool {
+        false
+    }
+
+    pub fn search_query(&self) -> Option<&str> {
+        None
+    }
+
+    pub fn search_match(&self) -> Option<usize> {
+        None
+    }
+
+    pub fn search_match_range(&self) -> Option<Range<usize>> {
+        None
+    }
+
+    pub fn search_match_indices(&self) -> Vec<usize> {
+        Vec::new()
+    }
+
+    pub fn fuzzy_search(&self) -> bool {
+        false
+    }
+
+    pub fn set_fuzzy_search(&mut self, _fuzzy: bool) {}
+
+    pub fn search_older(&mut self) -> bool {
+        false
+    }
+
+    pub fn search_newer(&mut self) -> bool {
+        false
+    }
+
+    pub fn accept_search(&mut self) {}
+
+    pub fn cancel_search(&mut self) {}
+
+    pub fn save_history<P: AsRef<std::path::Path>>(&mut self, _path: P) -> std::io::Result<()> {
+        Ok(())
+    }
+
+    pub fn load_history<P: AsRef<std::path::Path>>(&mut self, _path: P) -> std::io::Result<()> {
+        Ok(())
+    }
+
+    pub fn caret(&self) -> usize {
+        0
+    }
+
+    pub fn shown(&self) -> bool {
+        false
+    }
+
+    pub fn set_entry(&mut self, _entry: String) {}
+
+    pub fn receive_char(&mut self, _ch: char) {}
+
+    pub fn receive_char_if<F: Fn(char) -> bool>(&mut self, _ch: char, _filter: F) -> bool {
+        false
+    }
+
+    pub fn receive_text(&mut self, _text: &str) {}
+
+    pub fn receive_text_if<F: Fn(&str) -> bool>(&mut self, _text: &str, _filter: F) -> bool {
+        false
+    }
+
+    pub fn backspace(&mut self) {}
+    pub fn delete(&mut self) {}
+    pub fn delete_word_left(&mut self) {}
+    pub fn delete_word_right(&mut self) {}
+    pub fn kill_to_start(&mut self) {}
+    pub fn kill_to_end(&mut self) {}
+    pub fn yank(&mut self) -> bool {
+        false
+    }
+    pub fn yank_pop(&mut self) -> bool {
+        false
+    }
+    pub fn kills(&self) -> impl Iterator<Item = &str> {
+        std::iter::empty()
+    }
+    pub fn kill_ring_capacity(&self) -> usize {
+        0
+    }
+    pub fn set_kill_ring_capacity(&mut self, _capacity: usize) {}
+    pub fn undo(&mut self) -> bool {
+        false
+    }
+    pub fn redo(&mut self) -> bool {
+        false
+    }
+    pub fn undo_limit(&self) -> usize {
+        0
+    }
+    pub fn set_undo_limit(&mut self, _limit: usize) {}
+    pub fn clear(&mut self) {}
+    pub fn clear_history(&mut self) {}
+    pub fn up(&mut self) -> bool {
+        false
+    }
+    pub fn down(&mut self) -> bool {
+        false
+    }
+    pub fn up_deduped(&mut self) -> bool {
+        false
+    }
+    pub fn down_deduped(&mut self) -> bool {
+        false
+    }
+    pub fn up_prefix(&mut self) -> bool {
+        false
+    }
+    pub fn down_prefix(&mut self) -> bool {
+        false
+    }
+    pub fn left(&mut self) -> bool {
+        false
+    }
+    pub fn right(&mut self) -> bool {
+        false
+    }
+    pub fn home(&mut self) -> bool {
+        false
+    }
+    pub fn end(&mut self) -> bool {
+        false
+    }
+    pub fn word_left(&mut self) -> bool {
+        false
+    }
+    pub fn word_right(&mut self) -> bool {
+        false
+    }
+    pub fn word_boundary(&self) -> WordBoundary {
+        WordBoundary::default()
+    }
+    pub fn set_word_boundary(&mut self, _boundary: WordBoundary) {}
+    pub fn selection(&self) -> Option<Range<usize>> {
+        None
+    }
+    pub fn selected_text(&self) -> Option<&str> {
+        None
+    }
+    pub fn select_left(&mut self) -> bool {
+        false
+    }
+    pub fn select_right(&mut self) -> bool {
+        false
+    }
+    pub fn select_word_left(&mut self) -> bool {
+        false
+    }
+    pub fn select_word_right(&mut self) -> bool {
+        false
+    }
+    pub fn select_home(&mut self) -> bool {
+        false
+    }
+    pub fn select_end(&mut self) -> bool {
+        false
+    }
+    pub fn select_all(&mut self) {}
+    pub fn deselect(&mut self) {}
+    pub fn replace_selection(&mut self, _text: &str) -> bool {
+        false
+    }
+    pub fn copy<C: Clipboard>(&self, _clipboard: &mut C) -> bool {
+        false
+    }
+    pub fn cut<C: Clipboard>(&mut self, _clipboard: &mut C) -> bool {
+        false
+    }
+    pub fn paste<C: Clipboard>(&mut self, _clipboard: &mut C) -> bool {
+        false
+    }
+    pub fn complete<C: Completer + ?Sized>(&mut self, _completer: &mut C) -> bool {
+        false
+    }
+    pub fn completions(&self) -> &[String] {
+        &[]
+    }
+    pub fn completion_index(&self) -> Option<usize> {
+        None
+    }
+    pub fn select_completion(&mut self, _index: usize) -> bool {
+        false
+    }
+    pub fn cancel_completion(&mut self) {}
+    pub fn show(&mut self) {}
+    pub fn hide(&mut self) {}
+    pub fn toggle_shown(&mut self) {}
+}
+
+#[cfg(all(feature = "log", any(debug_assertions, feature = "force-enabled")))]
+impl Console {
+    /// Attaches a `ConsoleLogger`, so that `drain_log` moves its records into
+    /// the output, and the log filter can be changed from the console.
+    pub fn set_logger(&mut self, logger: ConsoleLogger) {
+        self.logger = Some(logger);
+    }
+
+    /// Returns the attached `ConsoleLogger`, if there is one.
+    pub fn logger(&self) -> Option<&ConsoleLogger> {
+        self.logger.as_ref()
+    }
+
+    /// Moves every record collected by the attached `ConsoleLogger` into the
+    /// output, and returns how many there were. Call this once a frame.
+    ///
+    /// Each record becomes output at the matching `Level`, with its target
+    /// before the message, like `game::ai: lost track of player`.
+    pub fn drain_log(&mut self) -> usize {
+        let records = match &self.logger {
+            Some(logger) => logger.drain(),
+            None => return 0,
+        };
+        for (level, text) in &records {
+            self.print(*level, text);
+        }
+        records.len()
+    }
+
+    /// Returns the filter of the attached `ConsoleLogger`, or the default
+    /// filter if there isn't one.
+    pub fn log_filter(&self) -> LogFilter {
+        self.logger
+            .as_ref()
+            .map(ConsoleLogger::filter)
+            .unwrap_or_default()
+    }
+
+    /// Replaces the filter of the attached `ConsoleLogger`. This affects
+    /// records logged from every thread from now on.
+    ///
+    /// Returns `false` if there is no logger attached.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use dbgcmd::{Console, ConsoleLogger, LogFilter};
+    ///
+    /// let mut console = Console::new();
+    /// console.set_logger(ConsoleLogger::new());
+    ///
+    /// // For example, from a `log_filter <directives>` command
+    /// let filter: LogFilter = "warn,game::ai=debug".parse().unwrap();
+    /// console.set_log_filter(filter.clone());
+    ///
+    /// if console.enabled() {
+    ///     assert_eq!(console.log_filter(), filter);
+    /// }
+    /// ```
+    pub fn set_log_filter(&mut self, filter: LogFilter) -> bool {
+        match &self.logger {
+            Some(logger) => {
+                logger.set_filter(filter);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(all(feature = "log", not(any(debug_assertions, feature = "force-enabled"))))]
+impl Console {
+    pub fn set_logger(&mut self, _logger: ConsoleLogger) {}
+
+    pub fn logger(&self) -> Option<&ConsoleLogger> {
+        None
+    }
+
+    pub fn drain_log(&mut self) -> usize {
+        0
+    }
+
+    pub fn log_filter(&self) -> LogFilter {
+        LogFilter::default()
+    }
+
+    pub fn set_log_filter(&mut self, _filter: LogFilter) -> bool {
+        false
+    }
+}
+
+#[cfg(all(feature = "tracing", any(debug_assertions, feature = "force-enabled")))]
+impl Console {
+    /// Attaches a `ConsoleLayer`, so that `drain_trace` moves the events it
+    /// captures into the output, and its filter can be changed from the
+    /// console.
+    pub fn set_trace_layer(&mut self, layer: ConsoleLayer) {
+        self.trace_layer = Some(layer);
+    }
+
+    /// Returns the attached `ConsoleLayer`, if there is one.
+    pub fn trace_layer(&self) -> Option<&ConsoleLayer> {
+        self.trace_layer.as_ref()
+    }
+
+    /// Moves every event captured by the attached `ConsoleLayer` into the
+    /// output, formatted with `TraceEvent`'s `Display` implementation, and
+    /// returns how many there were. Call this once a frame.
+    ///
+    /// To render the fields some other way, call `ConsoleLayer::drain`
+    /// yourself instead.
+    pub fn drain_trace(&mut self) -> usize {
+        let events = match &self.trace_layer {
+            Some(layer) => layer.drain(),
+            None => return 0,
+        };
+        for event in &events {
+            self.print(event.level.into(), &event.to_string());
+        }
+        events.len()
+    }
+
+    /// Returns the filter of the attached `ConsoleLayer`, or the default
+    /// filter if there isn't one.
+    pub fn trace_filter(&self) -> TraceFilter {
+        self.trace_layer
+            .as_ref()
+            .map(ConsoleLayer::filter)
+            .unwrap_or_default()
+    }
+
+    /// Replaces the filter of the attached `ConsoleLayer`. This affects events
+    /// from every thread from now on.
+    ///
+    /// Returns `false` if there is no layer attached.
+    pub fn set_trace_filter(&mut self, filter: TraceFilter) -> bool {
+        match &self.trace_layer {
+            Some(layer) => {
+                layer.set_filter(filter);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(all(
+    feature = "tracing",
+    not(any(debug_assertions, feature = "force-enabled"))
+))]
+impl Console {
+    pub fn set_trace_layer(&mut self, _layer: ConsoleLayer) {}
+
+    pub fn trace_layer(&self) -> Option<&ConsoleLayer> {
+        None
+    }
+
+    pub fn drain_trace(&mut self) -> usize {
+        0
+    }
+
+    pub fn trace_filter(&self) -> TraceFilter {
+        TraceFilter::default()
+    }
+
+    pub fn set_trace_filter(&mut self, _filter: TraceFilter) -> bool {
+        false
+    }
+}
+
+#[cfg(all(feature = "winit", any(debug_assertions, feature = "force-enabled")))]
+impl Console {
+    /// Handles a winit event, with Tab completing from the history using a
+    /// `DefaultCompleter` with no commands.
+    pub fn handle_winit_event(&mut self, event: &winit::event::Event<()>) {
+        self.handle_winit_event_with_completer(event, &mut DefaultCompleter::default());
+    }
+
+    /// Handles a winit event, with Tab completing using `completer`.
+    pub fn handle_winit_event_with_completer<C: Completer + ?Sized>(
+        &mut self,
+        event: &winit::event::Event<()>,
+        completer: &mut C,
+    ) {
+        use winit::{
+            event::{ElementState, Event, WindowEvent},
+            keyboard::{Key, NamedKey},
+        };
+
+        const VALID_CHARS: &str =
+            "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789 ._-\"'/\\~";
+
+        let event = match event {
+            Event::WindowEvent {
+                event: WindowEvent::ModifiersChanged(modifiers),
+                ..
+            } => {
+                self.modifiers = modifiers.state();
+                return;
+            }
+            Event::WindowEvent {
+                event: WindowEvent::KeyboardInput { event, .. },
+                ..
+            } if self.shown() && event.state == ElementState::Pressed => event,
+            _ => return,
+        };
+
+        let ctrl = self.modifiers.control_key();
+        let alt = self.modifiers.alt_key();
+        let shift = self.modifiers.shift_key();
+
+        match &event.logical_key {
+            Key::Character(c) if ctrl && c == "r" => {
+                if self.searching() {
+                    self.search_older();
+                } else {
+                    self.start_search();
+                }
+            }
+            Key::Character(c) if ctrl && c == "s" => {
+                self.search_newer();
+            }
+            Key::Character(c) if ctrl && c == "g" => {
+                self.cancel_search();
+            }
+            Key::Named(NamedKey::Escape) => {
+                self.cancel_search();
+            }
+            Key::Named(NamedKey::Enter) if self.searching() => {
+                self.accept_search();
+            }
+            Key::Character(c) if ctrl && c == "w" => {
+                self.delete_word_left();
+            }
+            Key::Character(c) if ctrl && c == "u" => {
+                self.kill_to_start();
+            }
+            Key::Character(c) if ctrl && c == "k" => {
+                self.kill_to_end();
+            }
+            Key::Character(c) if ctrl && c == "z" => {
+                self.undo();
+            }
+            Key::Character(c) if ctrl && c == "Z" => {
+                self.redo();
+            }
+            Key::Character(c) if ctrl && c == "y" => {
+                self.yank();
+            }
+            Key::Character(c) if alt && c == "y" => {
+                self.yank_pop();
+            }
+            Key::Character(c) if alt && c == "d" => {
+                self.delete_word_right();
+            }
+            Key::Named(NamedKey::Backspace) if ctrl => {
+                self.delete_word_left();
+            }
+            Key::Named(NamedKey::Delete) if ctrl => {
+                self.delete_word_right();
+            }
+            Key::Named(NamedKey::ArrowLeft) if ctrl && shift => {
+                self.select_word_left();
+            }
+            Key::Named(NamedKey::ArrowRight) if ctrl && shift => {
+                self.select_word_right();
+            }
+            Key::Named(NamedKey::ArrowLeft) if ctrl => {
+                self.word_left();
+            }
+            Key::Named(NamedKey::ArrowRight) if ctrl => {
+                if !self.word_right() {
+                    self.accept_suggestion_word();
+                }
+            }
+            Key::Named(NamedKey::ArrowLeft) if shift => {
+                self.select_left();
+            }
+            Key::Named(NamedKey::ArrowRight) if shift => {
+                self.select_right();
+            }
+            Key::Named(NamedKey::Home) if shift => {
+                self.select_home();
+            }
+            Key::Named(NamedKey::End) if shift => {
+                self.select_end();
+            }
+            Key::Named(NamedKey::Tab) => {
+                self.complete(completer);
+            }
+            Key::Named(NamedKey::Backspace) => {
+                self.backspace();
+            }
+            Key::Named(NamedKey::Delete) => {
+                self.delete();
+            }
+            Key::Named(NamedKey::ArrowUp) if alt => {
+                self.up_prefix();
+            }
+            Key::Named(NamedKey::ArrowDown) if alt => {
+                self.down_prefix();
+            }
+            Key::Named(NamedKey::ArrowUp) => {
+                self.up_deduped();
+            }
+            Key::Named(NamedKey::ArrowDown) => {
+                self.down_deduped();
+            }
+            Key::Named(NamedKey::ArrowLeft) => {
+                self.left();
+            }
+            Key::Named(NamedKey::PageUp) => {
+                self.page_up();
+            }
+            Key::Named(NamedKey::PageDown) => {
+                self.page_down();
+            }
+            Key::Named(NamedKey::ArrowRight) => {
+                if !self.right() {
+                    self.accept_suggestion();
+                }
+            }
+            Key::Named(NamedKey::Home) => {
+                self.home();
+            }
+            Key::Named(NamedKey::End) => {
+                if !self.end() {
+                    self.accept_suggestion();
+                }
+            }
+            _ => {
+                if let Some(text) = &event.text {
+                    let text = text.as_ref();
+                    if VALID_CHARS.contains(text) {
+                        self.receive_text(text);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(all(
+    feature = "winit",
+    not(any(debug_assertions, feature = "force-enabled"))
+))]
+impl Console {
+    pub fn handle_winit_event(&mut self, _event: &winit::event::Event<()>) {}
+
+    pub fn handle_winit_event_with_completer<C: Completer + ?Sized>(
+        &mut self,
+        _event: &winit::event::Event<()>,
+        _completer: &mut C,
+    ) {
+    }
+}
+
+#[cfg(test)]
+mod universal_tests {
+    use super::*;
+
+    #[test]
+    fn test_default_state() {
+        let console = Console::new();
+
+        assert!(!console.shown());
+        assert_eq!(console.entry(), "");
+        assert!(console.history().next().is_none());
+    }
+}
+
+#[cfg(test)]
+#[cfg(any(debug_assertions, feature = "force-enabled"))]
+mod debug_tests {
+    use super::*;
+
+    #[test]
+    fn show_hide_toggle() {
+        let mut console = Console::new();
+
+        console.show();
+        assert!(console.shown());
+
+        console.hide();
+        assert!(!console.shown());
+
+        console.toggle_shown();
+        assert!(console.shown());
+
+        console.toggle_shown();
+        assert!(!console.shown());
+    }
+
+    #[test]
+    fn history_grows() {
+        let mut console = Console::new();
+
+        console.set_entry("1".into());
+        console.confirm::<String>().unwrap();
+        assert_eq!(console.history().collect::<Vec<_>>(), vec!["1"]);
+
+        console.set_entry("2".into());
+        console.confirm::<String>().unwrap();
+        assert_eq!(console.history().collect::<Vec<_>>(), vec!["2", "1"]);
+
+        console.set_entry("3".into());
+        console.confirm::<String>().unwrap();
+        assert_eq!(console.history().collect::<Vec<_>>(), vec!["3", "2", "1"]);
+
+        console.set_entry("3".into());
+        console.confirm::<String>().unwrap();
+        assert_eq!(
+            console.history().collect::<Vec<_>>(),
+            vec!["3", "3", "2", "1"]
+        );
+        assert_eq!(
+            console.history_deduped().collect::<Vec<_>>(),
+            vec!["3", "2", "1"]
+        );
+    }
+
+    #[test]
+    fn cursor_movement() {
+        let mut console = Console::new();
+
+        {
+            console.set_entry("1".into());
+            console.confirm::<String>().unwrap();
+
+            console.set_entry("2".into());
+            console.confirm::<String>().unwrap();
+
+            console.set_entry("3".into());
+            console.confirm::<String>().unwrap();
+        }
+
+        assert_eq!(console.entry(), "");
+
+        console.up();
+        assert_eq!(console.entry(), "3");
+
+        console.up();
+        assert_eq!(console.entry(), "2");
+
+        console.down();
+        assert_eq!(console.entry(), "3");
+
+        console.down();
+        console.down();
+        console.down();
+        console.down();
+        assert_eq!(console.entry(), "");
+
+        console.up();
+        console.up();
+        console.up();
+        console.up();
+        assert_eq!(console.entry(), "1");
+    }
+
+    #[test]
+    fn deduped_cursor_movement() {
+        let mut console = Console::new();
+
+        {
+            console.set_entry("1".into());
+            console.confirm::<String>().unwrap();
+
+            console.set_entry("2".into());
+            console.confirm::<String>().unwrap();
+            console.set_entry("2".into());
+            console.confirm::<String>().unwrap();
+            console.set_entry("2".into());
+            console.confirm::<String>().unwrap();
+
+            console.set_entry("3".into());
+            console.confirm::<String>().unwrap();
+        }
+
+        assert_eq!(console.entry(), "");
+
+        console.up_deduped();
+        assert_eq!(console.entry(), "3");
+
+        console.up_deduped();
+        assert_eq!(console.entry(), "2");
+
+        console.up_deduped();
+        assert_eq!(console.entry(), "1");
+
+        console.down_deduped();
+        assert_eq!(console.entry(), "2");
+
+        console.down_deduped();
+        assert_eq!(console.entry(), "3");
+    }
+
+    #[test]
+    fn character_entry() {
+        let mut console = Console::new();
+
+        assert_eq!(console.entry(), "");
+        console.backspace();
+        assert_eq!(console.entry(), "");
+
+        console.receive_char('a');
+        console.receive_char('b');
+        console.receive_char('c');
+        assert_eq!(console.entry(), "abc");
+
+        console.receive_char_if('d', char::is_alphabetic);
+        assert_eq!(console.entry(), "abcd");
+
+        console.receive_char_if('0', char::is_alphabetic);
+        assert_eq!(console.entry(), "abcd");
+
+        console.backspace();
+        console.backspace();
+        assert_eq!(console.entry(), "ab");
+    }
+
+    #[test]
+    fn confirm() {
+        let mut console = Console::new();
+        console.set_entry("100".into());
+
+        assert_eq!(console.confirm::<usize>(), Ok(100));
+        assert_eq!(console.entry(), "");
+
+        console.set_entry("1a0".into());
+        assert_eq!(console.confirm::<usize>(), "1a0".parse::<usize>());
+        assert_eq!(console.entry(), "");
+    }
+
+    #[test]
+    fn confirm_tokens() {
+        let mut console = Console::new();
+        console.set_entry("spawn \"big orc\" 3".into());
+
+        let (raw, tokens) = console.confirm_tokens();
+        assert_eq!(raw, "spawn \"big orc\" 3");
+        let texts: Vec<_> = tokens.unwrap().into_iter().map(|t| t.text).collect();
+        assert_eq!(texts, vec!["spawn", "big orc", "3"]);
+        assert_eq!(console.entry(), "");
+        assert_eq!(console.history().collect::<Vec<_>>(), vec![raw.as_str()]);
+
+        console.set_entry("say 'oops".into());
+        let (_, tokens) = console.confirm_tokens();
+        assert_eq!(tokens.unwrap_err().offset(), 4);
+    }
+
+    #[test]
+    fn confirm_rendered() {
+        #[derive(Debug)]
+        struct Speed(f32);
+
+        impl std::str::FromStr for Speed {
+            type Err = Error;
+
+            fn from_str(s: &str) -> Result<Self, Error> {
+                let start = s.find(|ch: char| !ch.is_whitespace()).unwrap_or(0);
+                s.trim()
+                    .parse()
+                    .map(Speed)
+                    .map_err(|e| Error::custom(e).with_span(start..s.trim_end().len()))
+            }
+        }
+
+        let mut console = Console::new();
+        console.set_entry(" 2.5".into());
+        assert_eq!(console.confirm_rendered::<Speed>().unwrap().0, 2.5);
+
+        console.set_entry(" fast".into());
+        assert_eq!(
+            console.confirm_rendered::<Speed>().unwrap_err(),
+            " fast\n ^^^^ invalid float literal"
+        );
+    }
+
+    #[test]
+    fn completion() {
+        let mut console = Console::new();
+        confirm_all(&mut console, &["spawn 'big orc' 2", "spawn imp 1"]);
+        let mut completer = DefaultCompleter::new(vec!["save", "say"]);
+
+        console.set_entry("spawn b".into());
+        assert!(console.complete(&mut completer));
+        assert_eq!(console.entry(), "spawn big\\ orc ");
+        assert!(console.completions().is_empty());
+
+        console.set_entry("s".into());
+        console.complete(&mut completer);
+        assert_eq!(console.entry(), "s");
+        assert_eq!(console.completions(), ["save", "say", "spawn"]);
+
+        console.complete(&mut completer);
+        console.complete(&mut completer);
+        assert_eq!(console.entry(), "say");
+        assert_eq!(console.completion_index(), Some(1));
+        console.complete(&mut completer);
+        console.complete(&mut completer);
+        assert_eq!(console.entry(), "save");
+
+        assert!(console.select_completion(2));
+        assert_eq!(console.entry(), "spawn");
+        assert!(!console.select_completion(3));
+        assert_eq!(console.completion_index(), Some(2));
+
+        console.undo();
+        assert_eq!(console.entry(), "s");
+        assert!(console.completions().is_empty());
+
+        console.set_entry("sa ".into());
+        console.home();
+        console.right();
+        console.right();
+        console.complete(&mut completer);
+        assert_eq!(console.entry(), "sa ");
+        console.complete(&mut completer);
+        assert_eq!(console.entry(), "save ");
+        assert_eq!(console.caret(), 4);
+
+        console.receive_char('x');
+        assert!(console.completions().is_empty());
+
+        console.set_entry("zz".into());
+        assert!(!console.complete(&mut |_: &CompletionContext| Vec::new()));
+    }
+
+    #[test]
+    fn confirm_command() {
+        let mut registry = Registry::new();
+        registry.register("add", |args, total: &mut i32| {
+            for i in 0..args.len() {
+                *total += args.parse::<i32>(i)?;
+            }
+            Ok(total.to_string())
+        });
+
+        let mut total = 0;
+        let mut console = Console::new();
+        console.set_entry("add 1 2 3".into());
+
+        assert_eq!(
+            console.confirm_command(&mut registry, &mut total),
+            Outcome::Output("6".into())
+        );
+        assert_eq!(console.entry(), "");
+        assert_eq!(console.history().collect::<Vec<_>>(), vec!["add 1 2 3"]);
+    }
+
+    #[test]
+    fn can_edit_history_items() {
+        let mut console = Console::new();
+        console.set_entry("100".into());
+        assert_eq!(console.confirm::<usize>().unwrap(), 100);
+
+        console.up();
+        console.receive_char('0');
+        assert_eq!(console.confirm::<usize>().unwrap(), 1000);
+
+        console.up();
+        console.backspace();
+        console.backspace();
+        assert_eq!(console.confirm::<usize>().unwrap(), 10);
+    }
+
+    #[test]
+    fn caret_editing() {
+        let mut console = Console::new();
+        console.set_entry("spwn".into());
+        assert_eq!(console.caret(), 4);
+
+        assert!(console.home());
+        assert!(!console.left());
+        console.right();
+        console.right();
+        console.receive_char('a');
+        assert_eq!(console.entry(), "spawn");
+        assert_eq!(console.caret(), 3);
+
+        console.receive_text("wn sp");
+        assert_eq!(console.entry(), "spawn spwn");
+        assert_eq!(console.caret(), 8);
+
+        console.delete();
+        console.delete();
+        assert_eq!(console.entry(), "spawn sp");
+
+        console.left();
+        console.left();
+        console.backspace();
+        assert_eq!(console.entry(), "spawnsp");
+        assert_eq!(console.caret(), 5);
+
+        assert!(console.end());
+        assert!(!console.right());
+        assert_eq!(console.caret(), 7);
+    }
+
+    #[test]
+    fn caret_respects_graphemes() {
+        let mut console = Console::new();
+        console.set_entry("nai\u{308}ve".into());
+
+        console.left();
+        console.left();
+        assert_eq!(console.caret(), 5);
+        console.left();
+        assert_eq!(console.caret(), 2);
+
+        console.right();
+        console.backspace();
+        assert_eq!(console.entry(), "nave");
+
+        console.delete();
+        assert_eq!(console.entry(), "nae");
+    }
+
+    #[test]
+    fn caret_in_history_items() {
+        let mut console = Console::new();
+        console.set_entry("jump 10".into());
+        console.confirm::<String>().unwrap();
+
+        assert!(!console.down());
+        assert!(console.up());
+        assert!(!console.up());
+        assert_eq!(console.caret(), 7);
+
+        console.home();
+        console.receive_text("big");
+        console.receive_char(' ');
+        assert_eq!(console.entry(), "big jump 10");
+        assert_eq!(console.history().collect::<Vec<_>>(), vec!["jump 10"]);
+
+        console.up();
+        console.clear();
+        assert_eq!(console.entry(), "");
+        assert_eq!(console.caret(), 0);
+        assert_eq!(console.history().collect::<Vec<_>>(), vec!["jump 10"]);
+    }
+
+    #[test]
+    fn word_editing() {
+        let mut console = Console::new();
+        console.set_entry("spawn goblin  maps/a/b".into());
+
+        assert!(console.word_left());
+        assert_eq!(console.caret(), 14);
+        console.word_left();
+        console.word_right();
+        assert_eq!(console.caret(), 12);
+
+        console.delete_word_right();
+        assert_eq!(console.entry(), "spawn goblin");
+
+        console.delete_word_left();
+        assert_eq!(console.entry(), "spawn ");
+        assert_eq!(console.caret(), 6);
+
+        console.set_entry("spawn goblin maps/a/b".into());
+        console.set_word_boundary(WordBoundary::Punctuation);
+        console.delete_word_left();
+        assert_eq!(console.entry(), "spawn goblin maps/a/");
+        console.delete_word_left();
+        assert_eq!(console.entry(), "spawn goblin maps/");
+
+        console.word_left();
+        console.word_left();
+        console.kill_to_end();
+        assert_eq!(console.entry(), "spawn ");
+
+        console.left();
+        console.kill_to_start();
+        assert_eq!(console.entry(), " ");
+        assert_eq!(console.caret(), 0);
+    }
+
+    #[test]
+    fn undo_redo() {
+        let mut console = Console::new();
+        assert!(!console.undo());
+
+        console.receive_char('h');
+        console.receive_text("i");
+        console.receive_char(' ');
+        console.left();
+        console.receive_char('!');
+        assert_eq!(console.entry(), "hi! ");
+
+        assert!(console.undo());
+        assert_eq!(console.entry(), "hi ");
+        assert_eq!(console.caret(), 2);
+
+        console.clear();
+        console.set_entry("there".into());
+        assert!(console.undo());
+        assert_eq!(console.entry(), "");
+        assert!(console.undo());
+        assert_eq!(console.entry(), "hi ");
+
+        assert!(console.redo());
+        assert!(console.redo());
+        assert_eq!(console.entry(), "there");
+        assert!(!console.redo());
+
+        console.undo();
+        console.receive_char('x');
+        assert!(!console.redo());
+
+        while console.undo() {}
+        assert_eq!(console.entry(), "");
+    }
+
+    #[test]
+    fn undo_history_recall() {
+        let mut console = Console::new();
+        console.set_entry("old".into());
+        console.confirm::<String>().unwrap();
+        console.set_entry("older".into());
+        console.confirm::<String>().unwrap();
+        assert!(!console.undo());
+
+        console.receive_text("draft");
+        console.up();
+        console.up();
+        console.receive_char('!');
+        assert_eq!(console.entry(), "old!");
+
+        console.undo();
+        assert_eq!(console.entry(), "old");
+        console.undo();
+        assert_eq!(console.entry(), "draft");
+        assert_eq!(console.caret(), 5);
+        assert_eq!(console.history().collect::<Vec<_>>(), vec!["older", "old"]);
+    }
+
+    #[test]
+    fn undo_limit() {
+        let mut console = Console::new();
+        console.set_undo_limit(2);
+        assert_eq!(console.undo_limit(), 2);
+
+        for entry in ["a", "b", "c", "d"] {
+            console.set_entry(entry.into());
+        }
+
+        assert!(console.undo());
+        assert!(console.undo());
+        assert!(!console.undo());
+        assert_eq!(console.entry(), "b");
+
+        console.set_undo_limit(4);
+        for entry in ["e", "f", "g", "h"] {
+            console.set_entry(entry.into());
+        }
+        assert!(console.undo());
+        assert!(console.undo());
+        console.set_undo_limit(2);
+        while console.redo() {}
+        assert_eq!(console.entry(), "h");
+        assert!(console.undo());
+        assert!(console.undo());
+        assert!(!console.undo());
+        assert_eq!(console.entry(), "f");
+    }
+
+    #[test]
+    fn kill_and_yank() {
+        let mut console = Console::new();
+        assert!(!console.yank());
+
+        console.set_entry("tp player 10 20".into());
+        console.delete_word_left();
+        console.delete_word_left();
+        assert_eq!(console.entry(), "tp player ");
+        assert_eq!(console.kills().collect::<Vec<_>>(), vec!["10 20"]);
+
+        console.home();
+        console.kill_to_end();
+        assert_eq!(
+            console.kills().collect::<Vec<_>>(),
+            vec!["tp player ", "10 20"]
+        );
+
+        console.receive_text("give ");
+        assert!(!console.yank_pop());
+        assert!(console.yank());
+        assert_eq!(console.entry(), "give tp player ");
+
+        assert!(console.yank_pop());
+        assert_eq!(console.entry(), "give 10 20");
+        assert_eq!(console.caret(), 10);
+
+        assert!(console.yank_pop());
+        assert_eq!(console.entry(), "give tp player ");
+
+        console.undo();
+        assert_eq!(console.entry(), "give 10 20");
+
+        console.set_kill_ring_capacity(1);
+        assert_eq!(console.kill_ring_capacity(), 1);
+        assert_eq!(console.kills().collect::<Vec<_>>(), vec!["tp player "]);
+    }
+
+    #[test]
+    fn selection() {
+        let mut console = Console::new();
+        console.set_entry("spawn goblin 3".into());
+        assert_eq!(console.selection(), None);
+
+        console.word_left();
+        console.select_word_left();
+        assert_eq!(console.selection(), Some(6..13));
+        assert_eq!(console.selected_text(), Some("goblin "));
+
+        console.select_right();
+        assert_eq!(console.selected_text(), Some("oblin "));
+
+        assert!(console.left());
+        assert_eq!(console.selection(), None);
+        assert_eq!(console.caret(), 7);
+
+        console.select_end();
+        console.receive_text("rc");
+        assert_eq!(console.entry(), "spawn grc");
+        assert_eq!(console.selection(), None);
+
+        console.select_all();
+        assert_eq!(console.selection(), Some(0..9));
+        assert!(console.replace_selection("kill all"));
+        assert_eq!(console.entry(), "kill all");
+        assert!(!console.replace_selection("nothing"));
+
+        console.select_left();
+        console.select_left();
+        console.select_left();
+        console.backspace();
+        assert_eq!(console.entry(), "kill ");
+
+        console.undo();
+        assert_eq!(console.entry(), "kill all");
+        assert_eq!(console.selection(), None);
+    }
+
+    #[test]
+    fn clipboard() {
+        let mut console = Console::new();
+        let mut clipboard = MemoryClipboard::new();
+
+        console.set_entry("give gold 100".into());
+        assert!(!console.copy(&mut clipboard));
+        assert!(!console.paste(&mut clipboard));
+
+        console.select_word_left();
+        assert!(console.cut(&mut clipboard));
+        assert_eq!(clipboard.contents(), Some("100"));
+        assert_eq!(console.entry(), "give gold ");
+
+        console.home();
+        assert!(console.paste(&mut clipboard));
+        assert_eq!(console.entry(), "100give gold ");
+
+        console.select_home();
+        console.select_word_right();
+        assert!(console.copy(&mut clipboard));
+        assert_eq!(clipboard.contents(), Some("give"));
+    }
+
+    #[test]
+    fn history_limit() {
+        let mut console = Console::new();
+        for entry in ["1", "2", "3", "4"] {
+            console.set_entry(entry.into());
+            console.confirm::<String>().unwrap();
+        }
+
+        console.up();
+        console.up();
+        console.up();
+        console.set_history_options(HistoryOptions {
+            max_len: Some(2),
+            ..Default::default()
+        });
+        assert_eq!(console.history().collect::<Vec<_>>(), vec!["4", "3"]);
+        assert_eq!(console.entry(), "2");
 
-#[cfg(test)]
-mod universal_tests {
-    use super::*;
+        console.confirm::<String>().unwrap();
+        assert_eq!(console.history().collect::<Vec<_>>(), vec!["2", "4"]);
+    }
 
     #[test]
-    fn test_default_state() {
-        let console = Console::new();
+    fn history_filters() {
+        let mut console = Console::new();
+        console.set_history_options(HistoryOptions {
+            ignore_empty: true,
+            ignore_dups: true,
+            ignore_space: true,
+            ..Default::default()
+        });
+
+        for entry in ["a", "a", "  ", " secret", "b", "a", "a"] {
+            console.set_entry(entry.into());
+            console.confirm::<String>().unwrap();
+        }
 
-        assert!(!console.shown());
-        assert_eq!(console.entry(), "");
-        assert!(console.history().next().is_none());
+        assert_eq!(console.history().collect::<Vec<_>>(), vec!["a", "b", "a"]);
     }
-}
 
-#[cfg(test)]
-#[cfg(any(debug_assertions, feature = "force-enabled"))]
-mod debug_tests {
-    use super::*;
+    fn temp_path(name: &str) -> std::path::PathBuf {
+        let path = std::env::temp_dir().join(format!("dbgcmd-{}-{}", std::process::id(), name));
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    fn confirm_all(console: &mut Console, entries: &[&str]) {
+        for entry in entries {
+            console.set_entry((*entry).into());
+            console.confirm::<String>().unwrap();
+        }
+    }
 
     #[test]
-    fn show_hide_toggle() {
-        let mut console = Console::new();
+    fn save_and_load_history() {
+        let path = temp_path("save_and_load_history");
 
-        console.show();
-        assert!(console.shown());
+        let mut console = Console::new();
+        console.load_history(&path).unwrap();
+        assert_eq!(console.history_len(), 0);
 
-        console.hide();
-        assert!(!console.shown());
+        confirm_all(&mut console, &["first", "multi\nline\\"]);
+        console.save_history(&path).unwrap();
 
-        console.toggle_shown();
-        assert!(console.shown());
+        let mut loaded = Console::new();
+        confirm_all(&mut loaded, &["unsaved"]);
+        loaded.load_history(&path).unwrap();
+        assert_eq!(
+            loaded.history().collect::<Vec<_>>(),
+            vec!["unsaved", "multi\nline\\", "first"]
+        );
 
-        console.toggle_shown();
-        assert!(!console.shown());
+        std::fs::remove_file(&path).unwrap();
+        std::fs::remove_file(path.with_extension("lock")).unwrap();
     }
 
     #[test]
-    fn history_grows() {
-        let mut console = Console::new();
+    fn saving_history_merges() {
+        let path = temp_path("saving_history_merges");
+
+        let mut a = Console::new();
+        let mut b = Console::new();
+        confirm_all(&mut a, &["a1"]);
+        confirm_all(&mut b, &["b1"]);
+        confirm_all(&mut a, &["a2"]);
+
+        a.save_history(&path).unwrap();
+        b.save_history(&path).unwrap();
+        a.save_history(&path).unwrap();
+
+        confirm_all(&mut a, &["a3"]);
+        a.set_history_options(HistoryOptions {
+            max_len: Some(3),
+            ..Default::default()
+        });
+        a.save_history(&path).unwrap();
+
+        let mut loaded = Console::new();
+        loaded.load_history(&path).unwrap();
+        assert_eq!(loaded.history().collect::<Vec<_>>(), vec!["a3", "b1", "a2"]);
+
+        std::fs::remove_file(&path).unwrap();
+        std::fs::remove_file(path.with_extension("lock")).unwrap();
+    }
 
-        console.set_entry("1".into());
-        console.confirm::<String>().unwrap();
-        assert_eq!(console.history().collect::<Vec<_>>(), vec!["1"]);
+    #[test]
+    fn concurrent_history_saves() {
+        let path = temp_path("concurrent_history_saves");
+
+        let threads: Vec<_> = ["a", "b"]
+            .into_iter()
+            .map(|name| {
+                let path = path.clone();
+                std::thread::spawn(move || {
+                    let mut console = Console::new();
+                    for i in 0..50 {
+                        confirm_all(&mut console, &[&format!("{}{}", name, i)]);
+                        console.save_history(&path).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
 
-        console.set_entry("2".into());
-        console.confirm::<String>().unwrap();
-        assert_eq!(console.history().collect::<Vec<_>>(), vec!["2", "1"]);
+        let mut loaded = Console::new();
+        loaded.load_history(&path).unwrap();
+        for name in ["a", "b"] {
+            let saved: Vec<&str> = loaded
+                .history()
+                .filter(|entry| entry.starts_with(name))
+                .collect();
+            let expected: Vec<String> = (0..50).rev().map(|i| format!("{}{}", name, i)).collect();
+            assert_eq!(saved, expected);
+        }
 
-        console.set_entry("3".into());
-        console.confirm::<String>().unwrap();
-        assert_eq!(console.history().collect::<Vec<_>>(), vec!["3", "2", "1"]);
+        std::fs::remove_file(&path).unwrap();
+        std::fs::remove_file(path.with_extension("lock")).unwrap();
+    }
 
-        console.set_entry("3".into());
-        console.confirm::<String>().unwrap();
-        assert_eq!(
-            console.history().collect::<Vec<_>>(),
-            vec!["3", "3", "2", "1"]
+    #[test]
+    fn history_search() {
+        let mut console = Console::new();
+        confirm_all(
+            &mut console,
+            &["teleport 1 2", "spawn orc", "teleport 3 4", "heal"],
         );
-        assert_eq!(
-            console.history_deduped().collect::<Vec<_>>(),
-            vec!["3", "2", "1"]
+        console.receive_text("draft");
+
+        console.start_search();
+        assert!(console.searching());
+        assert_eq!(console.search_query(), Some(""));
+        assert_eq!(console.search_match(), None);
+        assert_eq!(console.entry(), "draft");
+
+        console.receive_text("port");
+        assert_eq!(console.search_match(), Some(1));
+        assert_eq!(console.entry(), "teleport 3 4");
+        assert_eq!(console.search_match_range(), Some(4..8));
+
+        console.receive_char(' ');
+        console.receive_char('1');
+        assert_eq!(console.search_match(), Some(3));
+        assert_eq!(console.search_match_range(), Some(4..10));
+        assert_eq!(console.caret(), 4);
+
+        assert!(!console.search_older());
+        console.backspace();
+        assert_eq!(console.search_query(), Some("port "));
+        assert_eq!(console.search_match(), Some(1));
+        assert!(console.search_older());
+        assert_eq!(console.search_match(), Some(3));
+        assert!(console.search_newer());
+        assert_eq!(console.search_match(), Some(1));
+
+        console.receive_char('x');
+        assert_eq!(console.search_match(), None);
+        assert_eq!(console.search_match_range(), None);
+        assert_eq!(console.entry(), "teleport 3 4");
+
+        console.cancel_search();
+        assert!(!console.searching());
+        assert_eq!(console.entry(), "draft");
+        assert_eq!(console.caret(), 5);
+    }
+
+    #[test]
+    fn search_edited_history() {
+        let mut console = Console::new();
+        confirm_all(&mut console, &["spawn orc", "heal"]);
+        console.up();
+        console.up();
+        console.home();
+        console.receive_text("re");
+
+        console.start_search();
+        console.receive_text("respawn");
+        assert_eq!(console.search_match(), Some(1));
+        assert_eq!(console.entry(), "respawn orc");
+        assert_eq!(console.search_match_range(), Some(0..7));
+
+        console.cancel_search();
+        console.start_search();
+        console.receive_text("orc");
+        assert_eq!(console.search_match(), Some(1));
+        assert_eq!(console.search_match_range(), Some(8..11));
+        assert_eq!(console.caret(), 8);
+    }
+
+    #[test]
+    fn fuzzy_history_search() {
+        let mut console = Console::new();
+        confirm_all(
+            &mut console,
+            &["spawn_enemy_at_cursor orc", "set_time 12", "save"],
         );
+        console.set_fuzzy_search(true);
+        assert!(console.fuzzy_search());
+
+        console.start_search();
+        console.receive_text("sea");
+        assert_eq!(console.search_match(), Some(2));
+        assert_eq!(console.search_match_indices(), vec![0, 6, 12]);
+        assert_eq!(console.search_match_range(), Some(0..13));
+
+        console.backspace();
+        console.receive_text("t");
+        assert_eq!(console.search_match(), Some(1));
+        assert_eq!(console.entry(), "set_time 12");
+        assert_eq!(console.search_match_indices(), vec![0, 1, 2]);
+
+        console.set_fuzzy_search(false);
+        assert_eq!(console.search_match_indices(), vec![0, 1, 2]);
+        assert_eq!(console.search_match_range(), Some(0..3));
     }
 
     #[test]
-    fn cursor_movement() {
+    fn autosuggestion() {
         let mut console = Console::new();
+        confirm_all(&mut console, &["spawn imp 3", "speed 2", "spawn orc"]);
+        assert_eq!(console.suggestion(), None);
 
-        {
-            console.set_entry("1".into());
-            console.confirm::<String>().unwrap();
+        console.receive_text("sp");
+        assert_eq!(console.suggestion(), Some("awn orc"));
+        console.receive_text("awn i");
+        assert_eq!(console.suggestion(), Some("mp 3"));
+        console.backspace();
+        assert_eq!(console.suggestion(), Some("orc"));
+        console.receive_text("x");
+        assert_eq!(console.suggestion(), None);
+        assert!(!console.accept_suggestion());
 
-            console.set_entry("2".into());
-            console.confirm::<String>().unwrap();
+        console.backspace();
+        console.left();
+        assert_eq!(console.suggestion(), None);
+        console.end();
+        assert!(console.accept_suggestion_word());
+        assert_eq!(console.entry(), "spawn orc");
+        assert_eq!(console.suggestion(), None);
 
-            console.set_entry("3".into());
-            console.confirm::<String>().unwrap();
-        }
+        console.clear();
+        console.receive_text("spawn i");
+        assert!(console.accept_suggestion_word());
+        assert_eq!(console.entry(), "spawn imp");
+        assert!(console.accept_suggestion());
+        assert_eq!(console.entry(), "spawn imp 3");
+        assert_eq!(console.caret(), 11);
 
-        assert_eq!(console.entry(), "");
+        console.undo();
+        assert_eq!(console.entry(), "spawn imp");
+        assert_eq!(console.suggestion(), Some(" 3"));
 
         console.up();
-        assert_eq!(console.entry(), "3");
+        assert_eq!(console.suggestion(), None);
+        console.start_search();
+        assert_eq!(console.suggestion(), None);
+    }
 
-        console.up();
-        assert_eq!(console.entry(), "2");
+    #[test]
+    fn suggestion_is_cached() {
+        let mut console = Console::new();
+        confirm_all(&mut console, &["spawn orc", "heal"]);
+        let cached = |console: &Console| console.suggester.get(&console.entry).is_some();
 
+        console.receive_text("sp");
+        console.up();
         console.down();
-        assert_eq!(console.entry(), "3");
+        assert!(cached(&console));
+        assert_eq!(console.suggestion(), Some("awn orc"));
 
-        console.down();
-        console.down();
-        console.down();
-        console.down();
+        while console.undo() {
+            assert!(cached(&console));
+        }
         assert_eq!(console.entry(), "");
+        while console.redo() {
+            assert!(cached(&console));
+        }
+        assert_eq!(console.suggestion(), Some("awn orc"));
 
-        console.up();
-        console.up();
-        console.up();
-        console.up();
-        assert_eq!(console.entry(), "1");
+        console.clear();
+        assert!(cached(&console));
+        console.receive_text("he");
+        console.set_history_options(HistoryOptions {
+            max_len: Some(1),
+            ..Default::default()
+        });
+        assert!(cached(&console));
+        assert_eq!(console.suggestion(), Some("al"));
     }
 
     #[test]
-    fn deduped_cursor_movement() {
+    fn output_scrollback() {
         let mut console = Console::new();
+        console.set_output_height(2);
+        console.set_output_options(OutputOptions {
+            max_lines: Some(4),
+            ..Default::default()
+        });
+
+        confirm_all(&mut console, &["status"]);
+        console.info("ok");
+        console.warn("low ammo");
+        console.error("out of ammo\nreloading");
+        let levels: Vec<Level> = console.output().map(|line| line.level).collect();
+        assert_eq!(
+            levels,
+            vec![Level::Info, Level::Warn, Level::Error, Level::Error]
+        );
 
-        {
-            console.set_entry("1".into());
-            console.confirm::<String>().unwrap();
+        let visible: Vec<&str> = console
+            .visible_output()
+            .map(|line| line.text.as_str())
+            .collect();
+        assert_eq!(visible, vec!["out of ammo", "reloading"]);
 
-            console.set_entry("2".into());
-            console.confirm::<String>().unwrap();
-            console.set_entry("2".into());
-            console.confirm::<String>().unwrap();
-            console.set_entry("2".into());
-            console.confirm::<String>().unwrap();
+        assert!(console.page_up());
+        assert_eq!(console.output_scroll(), 2);
+        assert!(!console.page_up());
+        assert_eq!(console.visible_output().next().unwrap().text, "ok");
 
-            console.set_entry("3".into());
-            console.confirm::<String>().unwrap();
-        }
+        console.receive_text("heal");
+        assert_eq!(console.output_scroll(), 2);
+        console.confirm::<String>().unwrap();
+        assert_eq!(console.output_scroll(), 0);
+        assert_eq!(console.output().last().unwrap().text, "heal");
+        assert_eq!(console.output_len(), 4);
+
+        console.set_output_options(OutputOptions {
+            echo: false,
+            ..Default::default()
+        });
+        confirm_all(&mut console, &["quiet"]);
+        assert_eq!(console.output().last().unwrap().text, "heal");
+
+        assert!(console.scroll_up(1));
+        assert!(console.page_down());
+        assert!(!console.scroll_down(1));
+        console.clear_output();
+        assert_eq!(console.output_len(), 0);
+    }
 
-        assert_eq!(console.entry(), "");
+    #[test]
+    fn confirm_with_cvars() {
+        let mut console = Console::new();
+        let mut cvars = Cvars::new();
+        cvars.register(Cvar::bool("r_wireframe", false));
 
-        console.up_deduped();
-        assert_eq!(console.entry(), "3");
+        console.set_entry("r_wireframe 1".into());
+        assert_eq!(console.confirm_with_cvars::<String>(&mut cvars), None);
+        assert_eq!(cvars.get_bool("r_wireframe"), Some(true));
 
-        console.up_deduped();
-        assert_eq!(console.entry(), "2");
+        console.set_entry("r_wireframe maybe".into());
+        assert_eq!(console.confirm_with_cvars::<String>(&mut cvars), None);
+        console.set_entry("spawn orc".into());
+        assert_eq!(
+            console.confirm_with_cvars::<String>(&mut cvars),
+            Some(Ok("spawn orc".into()))
+        );
 
-        console.up_deduped();
-        assert_eq!(console.entry(), "1");
+        let output: Vec<(Level, &str)> = console
+            .output()
+            .map(|line| (line.level, line.text.as_str()))
+            .collect();
+        assert_eq!(
+            output,
+            vec![
+                (Level::Echo, "r_wireframe 1"),
+                (Level::Info, "r_wireframe 1 (default 0)"),
+                (Level::Echo, "r_wireframe maybe"),
+                (Level::Error, "r_wireframe maybe"),
+                (
+                    Level::Error,
+                    "            ^^^^^ invalid value \"maybe\" for argument r_wireframe (expected bool)"
+                ),
+                (Level::Echo, "spawn orc"),
+            ]
+        );
+        assert_eq!(console.history().next(), Some("spawn orc"));
+    }
 
-        console.down_deduped();
-        assert_eq!(console.entry(), "2");
+    #[test]
+    fn aliases() {
+        let path = temp_path("aliases");
 
-        console.down_deduped();
-        assert_eq!(console.entry(), "3");
+        let mut console = Console::new();
+        console.set_alias("tp", "teleport $1 $2");
+        console.set_alias("home", "tp 0 0");
+        console.set_alias("spawn", "spawn --safe");
+        console.set_alias("ping", "pong");
+        console.set_alias("pong", "ping");
+        assert_eq!(console.alias("home"), Some("tp 0 0"));
+        assert_eq!(
+            console.aliases().collect::<Vec<_>>(),
+            vec![
+                ("home", "tp 0 0"),
+                ("ping", "pong"),
+                ("pong", "ping"),
+                ("spawn", "spawn --safe"),
+                ("tp", "teleport $1 $2")
+            ]
+        );
+        assert_eq!(console.expand_aliases("tp 3 4").unwrap(), "teleport 3 4");
+
+        console.set_entry("home".into());
+        assert_eq!(console.confirm::<String>(), Ok("teleport 0 0".into()));
+        console.set_entry("spawn orc".into());
+        assert_eq!(console.confirm::<String>(), Ok("spawn --safe orc".into()));
+        console.set_entry("ping".into());
+        assert_eq!(console.confirm::<String>(), Ok("ping".into()));
+        assert_eq!(
+            console.output().last().map(|line| line.text.as_str()),
+            Some("^^^^ alias cycle: ping -> pong -> ping")
+        );
+        console.set_entry("ping".into());
+        assert_eq!(
+            console.confirm_rendered::<String>(),
+            Err("ping\n^^^^ alias cycle: ping -> pong -> ping".into())
+        );
+        assert_eq!(
+            console.history().collect::<Vec<_>>(),
+            vec!["ping", "ping", "spawn orc", "home"]
+        );
+
+        console.save_aliases(&path).unwrap();
+        assert!(console.remove_alias("ping"));
+        assert!(!console.remove_alias("ping"));
+        console.clear_aliases();
+        assert_eq!(console.aliases().count(), 0);
+
+        std::fs::write(
+            &path,
+            std::fs::read_to_string(&path).unwrap() + "# note\n\nempty\n",
+        )
+        .unwrap();
+        console.set_alias("tp", "warp");
+        console.load_aliases(&path).unwrap();
+        assert_eq!(console.aliases().count(), 5);
+        assert_eq!(console.alias("tp"), Some("teleport $1 $2"));
+        assert_eq!(console.alias("empty"), None);
+
+        std::fs::remove_file(&path).unwrap();
     }
 
     #[test]
-    fn character_entry() {
+    fn accept_history_search() {
         let mut console = Console::new();
+        confirm_all(&mut console, &["spawn orc", "heal"]);
+        console.receive_text("draft");
+
+        console.start_search();
+        console.receive_text("orc");
+        console.accept_search();
+        assert!(!console.searching());
+        assert_eq!(console.entry(), "spawn orc");
+        assert_eq!(console.caret(), 9);
+
+        console.undo();
+        assert_eq!(console.entry(), "draft");
+
+        console.start_search();
+        console.receive_text("he");
+        console.home();
+        assert!(!console.searching());
+        console.receive_char('!');
+        assert_eq!(console.entry(), "!heal");
+    }
 
-        assert_eq!(console.entry(), "");
-        console.backspace();
-        assert_eq!(console.entry(), "");
-
-        console.receive_char('a');
-        console.receive_char('b');
-        console.receive_char('c');
-        assert_eq!(console.entry(), "abc");
+    #[test]
+    fn prefix_navigation() {
+        let mut console = Console::new();
+        confirm_all(
+            &mut console,
+            &["tp 1 1", "spawn orc", "tp 2 2", "tp 2 2", "heal", "tp 3 3"],
+        );
+        console.receive_text("tp 2");
 
-        console.receive_char_if('d', char::is_alphabetic);
-        assert_eq!(console.entry(), "abcd");
+        assert!(console.up_prefix());
+        assert_eq!(console.entry(), "tp 2 2");
+        assert!(!console.up_prefix());
+        assert_eq!(console.entry(), "tp 2 2");
 
-        console.receive_char_if('0', char::is_alphabetic);
-        assert_eq!(console.entry(), "abcd");
+        assert!(console.down_prefix());
+        assert_eq!(console.entry(), "tp 2");
+        assert!(!console.down_prefix());
 
         console.backspace();
         console.backspace();
-        assert_eq!(console.entry(), "ab");
+        assert!(console.up_prefix());
+        assert_eq!(console.entry(), "tp 3 3");
+        assert!(console.up_prefix());
+        assert_eq!(console.entry(), "tp 2 2");
+        assert!(console.up_prefix());
+        assert_eq!(console.entry(), "tp 1 1");
+        assert!(console.down_prefix());
+        assert_eq!(console.entry(), "tp 2 2");
+
+        console.up();
+        assert_eq!(console.entry(), "spawn orc");
+        console.up_prefix();
+        assert!(!console.up_prefix());
+        assert!(!console.down_prefix());
+        assert_eq!(console.entry(), "spawn orc");
     }
 
     #[test]
-    fn confirm() {
+    fn prefix_navigation_from_history() {
         let mut console = Console::new();
-        console.set_entry("100".into());
+        confirm_all(&mut console, &["spawn orc", "sp", "heal"]);
+        console.receive_text("hello");
 
-        assert_eq!(console.confirm::<usize>(), Ok(100));
-        assert_eq!(console.entry(), "");
+        console.up();
+        console.up();
+        assert_eq!(console.entry(), "sp");
+        assert!(console.up_prefix());
+        assert_eq!(console.entry(), "spawn orc");
+        assert!(console.down_prefix());
+        assert_eq!(console.entry(), "sp");
+        assert!(!console.down_prefix());
+        assert!(!console.down_prefix());
+        assert_eq!(console.entry(), "sp");
 
-        console.set_entry("1a0".into());
-        assert_eq!(console.confirm::<usize>(), "1a0".parse::<usize>());
-        assert_eq!(console.entry(), "");
+        console.down();
+        console.down();
+        assert_eq!(console.entry(), "hello");
     }
 
     #[test]
-    fn can_edit_history_items() {
+    fn draft_is_preserved() {
         let mut console = Console::new();
-        console.set_entry("100".into());
-        assert_eq!(console.confirm::<usize>().unwrap(), 100);
+        confirm_all(&mut console, &["old", "new"]);
 
+        console.receive_text("half typ");
         console.up();
-        console.receive_char('0');
-        assert_eq!(console.confirm::<usize>().unwrap(), 1000);
+        assert_eq!(console.entry(), "new");
+        assert_eq!(console.draft(), "half typ");
 
+        console.receive_char('!');
         console.up();
         console.backspace();
-        console.backspace();
-        assert_eq!(console.confirm::<usize>().unwrap(), 10);
+        assert_eq!(console.entry(), "ol");
+
+        console.down();
+        assert_eq!(console.entry(), "new!");
+        console.down();
+        assert_eq!(console.entry(), "half typ");
+        console.receive_char('e');
+
+        console.up();
+        console.up();
+        assert_eq!(console.entry(), "ol");
+        assert_eq!(console.history().collect::<Vec<_>>(), vec!["new", "old"]);
+
+        console.confirm::<String>().unwrap();
+        assert_eq!(
+            console.history().collect::<Vec<_>>(),
+            vec!["ol", "new", "old"]
+        );
+        assert_eq!(console.entry(), "");
+
+        console.up();
+        console.up();
+        assert_eq!(console.entry(), "new");
+    }
+
+    #[test]
+    fn empty_history_navigation() {
+        let mut console = Console::new();
+        console.set_entry("typed".into());
+
+        assert!(!console.up());
+        assert!(!console.up_deduped());
+        assert_eq!(console.entry(), "typed");
+        assert_eq!(console.caret(), 5);
+    }
+
+    #[cfg(feature = "log")]
+    #[test]
+    fn log_records() {
+        use log::{Level as LogLevel, LevelFilter, Log, Record};
+
+        let mut console = Console::new();
+        assert_eq!(console.drain_log(), 0);
+        assert!(!console.set_log_filter(LogFilter::new(LevelFilter::Debug)));
+
+        let logger = ConsoleLogger::new();
+        console.set_logger(logger.clone());
+        assert_eq!(console.logger(), Some(&logger));
+        assert!(console.set_log_filter("warn,game=debug".parse().unwrap()));
+        assert_eq!(logger.filter().level_for("game::ai"), LevelFilter::Debug);
+
+        let log = |level, target, message| {
+            logger.log(
+                &Record::builder()
+                    .level(level)
+                    .target(target)
+                    .args(format_args!("{}", message))
+                    .build(),
+            );
+        };
+        log(LogLevel::Info, "render", "hidden");
+        log(LogLevel::Debug, "game::ai", "lost track\nof player");
+        log(LogLevel::Error, "render", "device lost");
+
+        assert_eq!(console.drain_log(), 2);
+        assert_eq!(console.drain_log(), 0);
+        let lines: Vec<(Level, &str)> = console
+            .output()
+            .map(|line| (line.level, line.text.as_str()))
+            .collect();
+        assert_eq!(
+            lines,
+            vec![
+                (Level::Debug, "game::ai: lost track"),
+                (Level::Debug, "of player"),
+                (Level::Error, "render: device lost"),
+            ]
+        );
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn trace_events() {
+        use tracing_subscriber::layer::SubscriberExt;
+
+        let mut console = Console::new();
+        assert_eq!(console.drain_trace(), 0);
+        assert!(!console.set_trace_filter(TraceFilter::default()));
+
+        let layer = ConsoleLayer::new();
+        console.set_trace_layer(layer.clone());
+        assert_eq!(console.trace_layer(), Some(&layer));
+        let filter = TraceFilter::new(tracing_core::LevelFilter::DEBUG).has_field("entity");
+        assert!(console.set_trace_filter(filter.clone()));
+        assert_eq!(console.trace_filter(), filter);
+
+        let subscriber = tracing_subscriber::registry().with(layer);
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::debug!(target: "game", entity = 7, "spawned");
+            tracing::info!(target: "game", "no entity");
+            tracing::error!(target: "game", entity = 7, "stuck");
+        });
+
+        assert_eq!(console.drain_trace(), 2);
+        let lines: Vec<(Level, &str)> = console
+            .output()
+            .map(|line| (line.level, line.text.as_str()))
+            .collect();
+        assert_eq!(
+            lines,
+            vec![
+                (Level::Debug, "game: spawned entity=7"),
+                (Level::Error, "game: stuck entity=7"),
+            ]
+        );
     }
 }
 
@@ -623,30 +3885,186 @@ mod release_tests {
         assert_eq!(std::mem::size_of::<Console>(), 0);
     }
 
+    #[test]
+    fn registry_is_zst() {
+        assert_eq!(std::mem::size_of::<Registry<String>>(), 0);
+    }
+
     #[test]
     fn methods_do_nothing() {
         let mut console = Console::new();
         console.set_entry("command".into());
         assert_eq!(console.entry(), "");
+        assert_eq!(console.draft(), "");
 
         console.receive_char('a');
+        console.receive_text("bc");
         console.backspace();
+        console.delete();
+        console.delete_word_left();
+        console.delete_word_right();
+        console.kill_to_start();
+        console.kill_to_end();
+        assert!(!console.yank());
+        assert!(!console.yank_pop());
+        assert!(console.kills().next().is_none());
+        console.set_kill_ring_capacity(4);
+        assert_eq!(console.kill_ring_capacity(), 0);
         console.clear();
+        assert!(!console.undo());
+        assert!(!console.redo());
+        console.set_undo_limit(10);
+        assert_eq!(console.undo_limit(), 0);
         console.clear_history();
         assert!(!console.up());
         assert!(!console.down());
         assert!(!console.up_deduped());
         assert!(!console.down_deduped());
+        assert!(!console.up_prefix());
+        assert!(!console.down_prefix());
+        assert!(!console.left());
+        assert!(!console.right());
+        assert!(!console.home());
+        assert!(!console.end());
+        assert!(!console.word_left());
+        assert!(!console.word_right());
+        assert_eq!(console.caret(), 0);
+
+        console.set_word_boundary(WordBoundary::Punctuation);
+        assert_eq!(console.word_boundary(), WordBoundary::Whitespace);
+
+        let mut clipboard = MemoryClipboard::new();
+        clipboard.set("pasted".into());
+        console.select_all();
+        assert!(!console.select_left());
+        assert!(!console.select_end());
+        assert_eq!(console.selection(), None);
+        assert_eq!(console.selected_text(), None);
+        assert!(!console.replace_selection("text"));
+        assert!(!console.copy(&mut clipboard));
+        assert!(!console.cut(&mut clipboard));
+        assert!(!console.paste(&mut clipboard));
         console.show();
         console.hide();
         console.toggle_shown();
 
         assert_eq!(console.receive_char_if('a', |_| true), false);
 
+        console.set_output_height(10);
+        console.info("ignored");
+        assert_eq!(console.output_len(), 0);
+        assert_eq!(console.output().count(), 0);
+        assert_eq!(console.visible_output().count(), 0);
+        assert_eq!(console.output_height(), 0);
+        assert!(!console.page_up());
+        assert!(!console.scroll_down(1));
+        assert_eq!(console.output_scroll(), 0);
+
+        assert_eq!(console.suggestion(), None);
+        assert!(!console.accept_suggestion());
+        assert!(!console.accept_suggestion_word());
+
         assert_eq!(console.confirm::<String>(), "".parse());
+        let mut cvars = Cvars::new();
+        cvars.register(Cvar::int("fps_max", 60));
+        console.set_entry("fps_max 144".into());
+        assert_eq!(
+            console.confirm_with_cvars::<String>(&mut cvars),
+            Some(Ok(String::new()))
+        );
+        assert_eq!(cvars.get_int("fps_max"), Some(60));
+        assert_eq!(console.confirm_rendered::<String>(), Ok(String::new()));
+
+        console.set_alias("tp", "teleport $1 $2");
+        assert_eq!(console.alias("tp"), None);
+        assert!(!console.remove_alias("tp"));
+        assert!(console.aliases().next().is_none());
+        assert_eq!(console.expand_aliases("tp 1 2").unwrap(), "tp 1 2");
+        console.clear_aliases();
+        let alias_path = std::env::temp_dir().join("dbgcmd-release-aliases");
+        assert!(console.load_aliases(&alias_path).is_ok());
+        assert!(console.save_aliases(&alias_path).is_ok());
+        assert!(!alias_path.exists());
+
+        assert!(!console.complete(&mut DefaultCompleter::new(vec!["a"])));
+        assert!(console.completions().is_empty());
+        assert_eq!(console.completion_index(), None);
+        assert!(!console.select_completion(0));
+        console.cancel_completion();
+
+        console.set_fuzzy_search(true);
+        assert!(!console.fuzzy_search());
+        assert!(console.search_match_indices().is_empty());
+        assert_eq!(console.confirm_tokens(), (String::new(), Ok(Vec::new())));
+
+        let mut registry = Registry::new();
+        registry.register("cmd", |_, ran: &mut bool| {
+            *ran = true;
+            Ok(String::new())
+        });
+        let mut ran = false;
+        assert_eq!(registry.names().count(), 0);
+        assert_eq!(
+            console.confirm_command(&mut registry, &mut ran),
+            Outcome::Empty
+        );
+        assert_eq!(registry.dispatch("cmd", &mut ran), Outcome::Empty);
+        assert!(!ran);
+
+        console.set_history_options(HistoryOptions {
+            max_len: Some(1),
+            ..Default::default()
+        });
+        assert_eq!(console.history_options(), HistoryOptions::default());
+
+        let path = std::env::temp_dir().join("dbgcmd-release-history");
+        console.start_search();
+        console.receive_char('a');
+        assert!(!console.searching());
+        assert_eq!(console.search_query(), None);
+        assert_eq!(console.search_match(), None);
+        assert_eq!(console.search_match_range(), None);
+        assert!(!console.search_older());
+        assert!(!console.search_newer());
+        console.accept_search();
+        console.cancel_search();
+
+        assert!(console.load_history(&path).is_ok());
+        assert!(console.save_history(&path).is_ok());
+        assert!(!path.exists());
 
         assert_eq!(console.history_len(), 0);
         assert!(console.history().next().is_none());
         assert!(console.history_deduped().next().is_none());
     }
+
+    #[cfg(feature = "log")]
+    #[test]
+    fn log_does_nothing() {
+        use log::Log;
+
+        let mut console = Console::new();
+        let logger = ConsoleLogger::new();
+        console.set_logger(logger.clone());
+        assert!(!logger.enabled(&log::Metadata::builder().level(log::Level::Error).build()));
+        assert_eq!(console.logger(), None);
+        assert_eq!(console.drain_log(), 0);
+        assert!(!console.set_log_filter(LogFilter::default()));
+        assert_eq!(console.log_filter(), LogFilter::default());
+        assert_eq!(std::mem::size_of::<ConsoleLogger>(), 0);
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn trace_does_nothing() {
+        let mut console = Console::new();
+        let layer = ConsoleLayer::new();
+        console.set_trace_layer(layer.clone());
+        assert_eq!(console.trace_layer(), None);
+        assert_eq!(console.drain_trace(), 0);
+        assert!(!console.set_trace_filter(TraceFilter::default()));
+        assert_eq!(console.trace_filter(), TraceFilter::default());
+        assert!(layer.drain().is_empty());
+        assert_eq!(std::mem::size_of::<ConsoleLayer>(), 0);
+    }
 }
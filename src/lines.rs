@@ -0,0 +1,139 @@
+//! Reading and writing files of lines, such as history and config files.
+
+/// Escapes an entry so that it fits on a single line of a file.
+#[cfg(any(debug_assertions, feature = "force-enabled"))]
+pub(crate) fn escape(entry: &str) -> String {
+    let mut escaped = String::with_capacity(entry.len());
+    for ch in entry.chars() {
+        match ch {
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            ch => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+/// Reverses `escape`. Unknown escape sequences are kept as they are.
+#[cfg(any(debug_assertions, feature = "force-enabled"))]
+pub(crate) fn unescape(line: &str) -> String {
+    let mut entry = String::with_capacity(line.len());
+    let mut chars = line.chars();
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            entry.push(ch);
+            continue;
+        }
+        match chars.next() {
+            Some('\\') => entry.push('\\'),
+            Some('n') => entry.push('\n'),
+            Some('r') => entry.push('\r'),
+            Some(other) => {
+                entry.push('\\');
+                entry.push(other);
+            }
+            None => entry.push('\\'),
+        }
+    }
+    entry
+}
+
+/// Reads the lines of a file written by `write_escaped`, unescaping each one.
+/// A missing file is treated as an empty one.
+#[cfg(any(debug_assertions, feature = "force-enabled"))]
+pub(crate) fn read_escaped(path: &std::path::Path) -> std::io::Result<Vec<String>> {
+    Ok(read(path)?.iter().map(|line| unescape(line)).collect())
+}
+
+/// Writes each line to the file, escaped, the same way as `write`.
+#[cfg(any(debug_assertions, feature = "force-enabled"))]
+pub(crate) fn write_escaped<'a, I>(path: &std::path::Path, lines: I) -> std::io::Result<()>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let lines: Vec<String> = lines.into_iter().map(escape).collect();
+    write(path, lines.iter().map(String::as_str))
+}
+
+/// Reads the lines of a file as they are. A missing file is treated as an
+/// empty one.
+pub(crate) fn read(path: &std::path::Path) -> std::io::Result<Vec<String>> {
+    match std::fs::read_to_string(path) {
+        Ok(contents) => Ok(contents.lines().map(str::to_owned).collect()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e),
+    }
+}
+
+/// Writes each line to the file as it is. The file is written to a temporary
+/// file alongside it first and then renamed into place, so that the file is
+/// never left half-written. Each write uses its own temporary file, so writers
+/// in other threads or processes can't clobber it.
+pub(crate) fn write<'a, I>(path: &std::path::Path, lines: I) -> std::io::Result<()>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    use std::io::Write;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+    let mut temp_path = path.as_os_str().to_owned();
+    temp_path.push(format!(
+        ".{}.{}.tmp",
+        std::process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+
+    let result = (|| {
+        let mut file = std::io::BufWriter::new(std::fs::File::create(&temp_path)?);
+        for line in lines {
+            writeln!(file, "{}", line)?;
+        }
+        file.into_inner()
+            .map_err(std::io::IntoInnerError::into_error)?
+            .sync_all()?;
+        std::fs::rename(&temp_path, path)
+    })();
+    if result.is_err() {
+        let _ = std::fs::remove_file(&temp_path);
+    }
+    result
+}
+
+/// Takes an exclusive advisory lock for changing the file at `path`, waiting
+/// until any other holder releases it. The lock is released when the returned
+/// file is dropped.
+///
+/// The lock is held on a separate `.lock` file next to `path`, since `write`
+/// replaces the file itself.
+#[cfg(any(debug_assertions, feature = "force-enabled"))]
+pub(crate) fn lock(path: &std::path::Path) -> std::io::Result<std::fs::File> {
+    let mut lock_path = path.as_os_str().to_owned();
+    lock_path.push(".lock");
+    let file = std::fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(lock_path)?;
+    file.lock()?;
+    Ok(file)
+}
+
+#[cfg(test)]
+#[cfg(any(debug_assertions, feature = "force-enabled"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escape_round_trip() {
+        let entry = "echo \"a\\nb\"\nsecond line\r\\";
+        let escaped = escape(entry);
+
+        assert!(!escaped.contains('\n'));
+        assert!(!escaped.contains('\r'));
+        assert_eq!(unescape(&escaped), entry);
+        assert_eq!(unescape("\\q\\"), "\\q\\");
+    }
+}
@@ -0,0 +1,455 @@
+//! Forwarding records from the `log` crate into the output of a `Console`.
+//!
+//! Records can be logged from any thread, so `ConsoleLogger` only collects
+//! them. Attach it to your `Console` with `Console::set_logger`, and call
+//! `Console::drain_log` once a frame to move them into the output.
+
+use std::fmt;
+use std::str::FromStr;
+
+use log::LevelFilter;
+
+#[cfg(any(debug_assertions, feature = "force-enabled"))]
+use std::collections::VecDeque;
+
+#[cfg(any(debug_assertions, feature = "force-enabled"))]
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use crate::error::{Error, ErrorKind};
+use crate::output::Level;
+
+impl From<log::Level> for Level {
+    fn from(level: log::Level) -> Self {
+        match level {
+            log::Level::Error => Level::Error,
+            log::Level::Warn => Level::Warn,
+            log::Level::Info => Level::Info,
+            log::Level::Debug => Level::Debug,
+            log::Level::Trace => Level::Trace,
+        }
+    }
+}
+
+/// Which log records are shown, by level and by target.
+///
+/// Each target directive applies to that target and every module below it, so
+/// `game` also covers `game::ai`. When several directives match, the longest
+/// one wins, and when none do, the default level is used.
+///
+/// This can be parsed from a string of comma-separated directives, in the same
+/// format as `env_logger`, so that it's easy to change from a console command.
+///
+/// # Examples
+///
+/// ```rust
+/// use dbgcmd::LogFilter;
+/// use log::LevelFilter;
+///
+/// let filter: LogFilter = "warn,game=info,game::ai=trace".parse().unwrap();
+///
+/// assert_eq!(filter.level_for("render"), LevelFilter::Warn);
+/// assert_eq!(filter.level_for("game::physics"), LevelFilter::Info);
+/// assert_eq!(filter.level_for("game::ai::path"), LevelFilter::Trace);
+/// assert_eq!(filter.to_string(), "warn,game=info,game::ai=trace");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct LogFilter {
+    level: LevelFilter,
+    targets: Vec<(String, LevelFilter)>,
+}
+
+impl Default for LogFilter {
+    fn default() -> Self {
+        LogFilter::new(LevelFilter::Info)
+    }
+}
+
+impl LogFilter {
+    /// Creates a filter that shows records at `level` or more severe, from
+    /// every target.
+    pub fn new(level: LevelFilter) -> Self {
+        LogFilter {
+            level,
+            targets: Vec::new(),
+        }
+    }
+
+    /// The level for targets that no directive matches.
+    pub fn level(&self) -> LevelFilter {
+        self.level
+    }
+
+    pub fn set_level(&mut self, level: LevelFilter) {
+        self.level = level;
+    }
+
+    /// Sets the level for `target` and the modules below it, replacing any
+    /// previous directive for the same target.
+    pub fn set_target(&mut self, target: &str, level: LevelFilter) {
+        match self.targets.iter_mut().find(|(t, _)| t == target) {
+            Some((_, existing)) => *existing = level,
+            None => self.targets.push((target.to_owned(), level)),
+        }
+    }
+
+    /// The same as `set_target`, but can be chained.
+    pub fn target(mut self, target: &str, level: LevelFilter) -> Self {
+        self.set_target(target, level);
+        self
+    }
+
+    /// Removes the directive for `target`, so that it falls back to a shorter
+    /// directive or the default level. Returns `false` if there wasn't one.
+    pub fn remove_target(&mut self, target: &str) -> bool {
+        let len = self.targets.len();
+        self.targets.retain(|(t, _)| t != target);
+        self.targets.len() != len
+    }
+
+    /// Returns the directives for individual targets, in the order they were
+    /// added.
+    pub fn targets(&self) -> impl Iterator<Item = (&str, LevelFilter)> {
+        self.targets.iter().map(|(t, level)| (t.as_str(), *level))
+    }
+
+    /// Returns the level that applies to records from `target`.
+    pub fn level_for(&self, target: &str) -> LevelFilter {
+        self.targets
+            .iter()
+            .filter(|(t, _)| {
+                target
+                    .strip_prefix(t.as_str())
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+            })
+            .max_by_key(|(t, _)| t.len())
+            .map_or(self.level, |(_, level)| *level)
+    }
+
+    /// Whether a record at `level` from `target` is shown.
+    pub fn allows(&self, level: log::Level, target: &str) -> bool {
+        level <= self.level_for(target)
+    }
+
+    /// The most verbose level that any target is shown at.
+    pub fn max_level(&self) -> LevelFilter {
+        self.targets
+            .iter()
+            .map(|(_, level)| *level)
+            .fold(self.level, Ord::max)
+    }
+}
+
+fn parse_level(text: &str, offset: usize) -> Result<LevelFilter, Error> {
+    text.trim().parse().map_err(|_| {
+        Error::new(
+            ErrorKind::InvalidValue {
+                name: "level".to_owned(),
+                value: text.trim().to_owned(),
+                expected: "log level".to_owned(),
+            },
+            Some(offset..offset + text.len()),
+        )
+    })
+}
+
+impl FromStr for LogFilter {
+    type Err = Error;
+
+    /// Parses directives like `warn,game=debug`. A directive without `=` sets
+    /// the default level, and the default level is `info` if none is given.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut filter = LogFilter::default();
+        let mut offset = 0;
+        for directive in s.split(',') {
+            match directive.split_once('=') {
+                Some((target, level)) => {
+                    let level = parse_level(level, offset + target.len() + 1)?;
+                    filter.set_target(target.trim(), level);
+                }
+                None if directive.trim().is_empty() => (),
+                None => filter.level = parse_level(directive, offset)?,
+            }
+            offset += directive.len() + 1;
+        }
+        Ok(filter)
+    }
+}
+
+impl fmt::Display for LogFilter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.level.as_str().to_lowercase())?;
+        for (target, level) in &self.targets {
+            write!(f, ",{}={}", target, level.as_str().to_lowercase())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(any(debug_assertions, feature = "force-enabled"))]
+#[derive(Debug)]
+struct Shared {
+    records: VecDeque<(Level, String)>,
+    capacity: usize,
+    filter: LogFilter,
+    installed: bool,
+}
+
+/// A `log::Log` implementation that collects records for a `Console`.
+///
+/// This is a cheap handle to a buffer shared between threads, so clone it to
+/// keep one for the `Console` before installing it. Only the newest `capacity`
+/// records are kept until they are drained.
+///
+/// In release mode, unless the `force-enabled` feature is on, this ignores
+/// every record, and `install` does nothing.
+///
+/// # Examples
+///
+/// ```rust
+/// use dbgcmd::{Console, ConsoleLogger};
+///
+/// let logger = ConsoleLogger::new();
+/// logger.clone().install().unwrap();
+///
+/// let mut console = Console::new();
+/// console.set_logger(logger);
+///
+/// log::warn!("low on memory");
+///
+/// // Once a frame, on the thread that owns the console
+/// console.drain_log();
+///
+/// if console.enabled() {
+///     assert!(console.output().any(|line| line.text.ends_with("low on memory")));
+/// }
+/// ```
+#[derive(Debug, Clone)]
+#[cfg(any(debug_assertions, feature = "force-enabled"))]
+pub struct ConsoleLogger {
+    shared: Arc<Mutex<Shared>>,
+}
+
+#[cfg(any(debug_assertions, feature = "force-enabled"))]
+impl Default for ConsoleLogger {
+    fn default() -> Self {
+        ConsoleLogger::with_capacity(ConsoleLogger::DEFAULT_CAPACITY)
+    }
+}
+
+#[cfg(any(debug_assertions, feature = "force-enabled"))]
+impl PartialEq for ConsoleLogger {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.shared, &other.shared)
+    }
+}
+
+#[cfg(any(debug_assertions, feature = "force-enabled"))]
+impl Eq for ConsoleLogger {}
+
+#[cfg(any(debug_assertions, feature = "force-enabled"))]
+impl ConsoleLogger {
+    /// The number of records kept by `new` until they are drained.
+    pub const DEFAULT_CAPACITY: usize = 1024;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        ConsoleLogger {
+            shared: Arc::new(Mutex::new(Shared {
+                records: VecDeque::new(),
+                capacity,
+                filter: LogFilter::default(),
+                installed: false,
+            })),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Shared> {
+        self.shared.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Installs this as the global logger with `log::set_boxed_logger`.
+    ///
+    /// This fails if another logger has already been installed.
+    pub fn install(self) -> Result<(), log::SetLoggerError> {
+        let handle = self.clone();
+        log::set_boxed_logger(Box::new(self))?;
+        let mut shared = handle.lock();
+        shared.installed = true;
+        log::set_max_level(shared.filter.max_level());
+        Ok(())
+    }
+
+    pub fn filter(&self) -> LogFilter {
+        self.lock().filter.clone()
+    }
+
+    /// Replaces the filter. If this is the installed logger, the global
+    /// maximum level is updated to match, so that filtered records cost
+    /// nothing to log.
+    pub fn set_filter(&self, filter: LogFilter) {
+        let mut shared = self.lock();
+        if shared.installed {
+            log::set_max_level(filter.max_level());
+        }
+        shared.filter = filter;
+    }
+
+    /// Removes and returns every record collected so far, oldest first.
+    pub(crate) fn drain(&self) -> VecDeque<(Level, String)> {
+        std::mem::take(&mut self.lock().records)
+    }
+}
+
+#[cfg(any(debug_assertions, feature = "force-enabled"))]
+impl log::Log for ConsoleLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        self.lock()
+            .filter
+            .allows(metadata.level(), metadata.target())
+    }
+
+    fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let text = format!("{}: {}", record.target(), record.args());
+        let mut shared = self.lock();
+        if shared.records.len() >= shared.capacity {
+            shared.records.pop_front();
+        }
+        if shared.capacity > 0 {
+            shared.records.push_back((record.level().into(), text));
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg(not(any(debug_assertions, feature = "force-enabled")))]
+pub struct ConsoleLogger;
+
+#[cfg(not(any(debug_assertions, feature = "force-enabled")))]
+impl ConsoleLogger {
+    pub const DEFAULT_CAPACITY: usize = 1024;
+
+    pub fn new() -> Self {
+        ConsoleLogger
+    }
+
+    pub fn with_capacity(_capacity: usize) -> Self {
+        ConsoleLogger
+    }
+
+    pub fn install(self) -> Result<(), log::SetLoggerError> {
+        Ok(())
+    }
+
+    pub fn filter(&self) -> LogFilter {
+        LogFilter::default()
+    }
+
+    pub fn set_filter(&self, _filter: LogFilter) {}
+}
+
+#[cfg(not(any(debug_assertions, feature = "force-enabled")))]
+impl log::Log for ConsoleLogger {
+    fn enabled(&self, _metadata: &log::Metadata) -> bool {
+        false
+    }
+
+    fn log(&self, _record: &log::Record) {}
+
+    fn flush(&self) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filtering() {
+        let filter = LogFilter::new(LevelFilter::Warn)
+            .target("game", LevelFilter::Debug)
+            .target("game::ai", LevelFilter::Off);
+
+        assert!(filter.allows(log::Level::Warn, "render"));
+        assert!(!filter.allows(log::Level::Info, "render"));
+        assert!(filter.allows(log::Level::Debug, "game"));
+        assert!(filter.allows(log::Level::Debug, "game::physics"));
+        assert!(!filter.allows(log::Level::Error, "game::ai::path"));
+        assert!(!filter.allows(log::Level::Debug, "gameplay"));
+        assert_eq!(filter.max_level(), LevelFilter::Debug);
+    }
+
+    #[test]
+    fn parsing() {
+        let filter: LogFilter = " error , game = TRACE,".parse().unwrap();
+        assert_eq!(filter.level(), LevelFilter::Error);
+        assert_eq!(
+            filter.targets().collect::<Vec<_>>(),
+            vec![("game", LevelFilter::Trace)]
+        );
+        assert_eq!("".parse::<LogFilter>().unwrap(), LogFilter::default());
+
+        let error = "warn,game=loud".parse::<LogFilter>().unwrap_err();
+        assert_eq!(
+            error.render("warn,game=loud"),
+            "warn,game=loud\n          ^^^^ invalid value \"loud\" for argument level (expected log level)"
+        );
+    }
+
+    #[cfg(any(debug_assertions, feature = "force-enabled"))]
+    #[test]
+    fn collects_records() {
+        use log::Log;
+
+        let logger = ConsoleLogger::with_capacity(2);
+        logger.set_filter(LogFilter::new(LevelFilter::Info).target("noisy", LevelFilter::Off));
+        for (level, target, message) in [
+            (log::Level::Info, "game", "one"),
+            (log::Level::Debug, "game", "hidden"),
+            (log::Level::Error, "noisy", "hidden"),
+            (log::Level::Warn, "game", "two"),
+            (log::Level::Error, "game", "three"),
+        ] {
+            logger.log(
+                &log::Record::builder()
+                    .level(level)
+                    .target(target)
+                    .args(format_args!("{}", message))
+                    .build(),
+            );
+        }
+
+        let records: Vec<_> = logger.clone().drain().into_iter().collect();
+        assert_eq!(
+            records,
+            vec![
+                (Level::Warn, "game: two".to_owned()),
+                (Level::Error, "game: three".to_owned())
+            ]
+        );
+        assert!(logger.drain().is_empty());
+    }
+
+    #[cfg(any(debug_assertions, feature = "force-enabled"))]
+    #[test]
+    fn install_twice() {
+        let first = ConsoleLogger::new();
+        first.set_filter(LogFilter::new(LevelFilter::Warn));
+        first.clone().install().unwrap();
+        assert_eq!(log::max_level(), LevelFilter::Warn);
+
+        let second = ConsoleLogger::new();
+        assert!(second.clone().install().is_err());
+        second.set_filter(LogFilter::new(LevelFilter::Trace));
+        assert_eq!(log::max_level(), LevelFilter::Warn);
+
+        first.set_filter(LogFilter::new(LevelFilter::Error));
+        assert_eq!(log::max_level(), LevelFilter::Error);
+    }
+}
@@ -0,0 +1,205 @@
+//! The scrollback buffer of output lines shown above the entry.
+
+#[cfg(any(debug_assertions, feature = "force-enabled"))]
+use std::collections::VecDeque;
+
+/// The severity of a line of output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Level {
+    /// A confirmed entry, echoed back into the output.
+    Echo,
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+/// A single line in the output of a `Console`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct OutputLine {
+    pub level: Level,
+
+    /// The text of the line. This never contains a newline.
+    pub text: String,
+}
+
+/// Controls how the output of a `Console` is kept.
+///
+/// The default keeps every line forever, and echoes each confirmed entry.
+///
+/// # Examples
+///
+/// ```rust
+/// use dbgcmd::{Console, OutputOptions};
+///
+/// let mut console = Console::new();
+/// console.set_output_options(OutputOptions {
+///     max_lines: Some(1000),
+///     ..Default::default()
+/// });
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct OutputOptions {
+    /// The maximum number of lines to keep. Once this is exceeded, the oldest
+    /// lines are evicted. `None` means there is no limit.
+    pub max_lines: Option<usize>,
+
+    /// Add each confirmed entry to the output as a `Level::Echo` line.
+    pub echo: bool,
+}
+
+impl Default for OutputOptions {
+    fn default() -> Self {
+        OutputOptions {
+            max_lines: None,
+            echo: true,
+        }
+    }
+}
+
+/// The output lines, and how far they are scrolled back.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg(any(debug_assertions, feature = "force-enabled"))]
+pub(crate) struct Scrollback {
+    lines: VecDeque<OutputLine>,
+    height: usize,
+    scroll: usize,
+}
+
+#[cfg(any(debug_assertions, feature = "force-enabled"))]
+impl Scrollback {
+    pub fn lines(&self) -> &VecDeque<OutputLine> {
+        &self.lines
+    }
+
+    /// The number of lines that fit in the view, or `0` if every line does.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn set_height(&mut self, height: usize) {
+        self.height = height;
+        self.scroll = self.scroll.min(self.max_scroll());
+    }
+
+    /// How many lines the view is scrolled back from the newest line.
+    pub fn scroll(&self) -> usize {
+        self.scroll
+    }
+
+    fn max_scroll(&self) -> usize {
+        if self.height == 0 {
+            0
+        } else {
+            self.lines.len().saturating_sub(self.height)
+        }
+    }
+
+    /// Scrolls towards older lines if `up`, or newer lines otherwise. Returns
+    /// `false` if the view could not move.
+    pub fn scroll_by(&mut self, up: bool, lines: usize) -> bool {
+        let scroll = if up {
+            self.scroll.saturating_add(lines).min(self.max_scroll())
+        } else {
+            self.scroll.saturating_sub(lines)
+        };
+        let moved = scroll != self.scroll;
+        self.scroll = scroll;
+        moved
+    }
+
+    pub fn scroll_to_bottom(&mut self) {
+        self.scroll = 0;
+    }
+
+    /// The lines currently in view, oldest first.
+    pub fn visible(&self) -> std::collections::vec_deque::Iter<'_, OutputLine> {
+        let end = self.lines.len() - self.scroll;
+        let start = if self.height == 0 {
+            0
+        } else {
+            end.saturating_sub(self.height)
+        };
+        self.lines.range(start..end)
+    }
+
+    /// Adds `text` as one line per line of text. If the view is scrolled back,
+    /// it stays on the same lines.
+    pub fn push(&mut self, level: Level, text: &str, max_lines: Option<usize>) {
+        let text = text.strip_suffix('\n').unwrap_or(text);
+        for line in text.split('\n') {
+            let line = line.strip_suffix('\r').unwrap_or(line);
+            self.lines.push_back(OutputLine {
+                level,
+                text: line.to_owned(),
+            });
+            if self.scroll > 0 {
+                self.scroll += 1;
+            }
+        }
+        self.truncate(max_lines);
+    }
+
+    /// Evicts the oldest lines beyond `max_lines`.
+    pub fn truncate(&mut self, max_lines: Option<usize>) {
+        if let Some(max_lines) = max_lines {
+            let excess = self.lines.len().saturating_sub(max_lines);
+            self.lines.drain(..excess);
+        }
+        self.scroll = self.scroll.min(self.max_scroll());
+    }
+
+    pub fn clear(&mut self) {
+        self.lines.clear();
+        self.scroll = 0;
+    }
+}
+
+#[cfg(test)]
+#[cfg(any(debug_assertions, feature = "force-enabled"))]
+mod tests {
+    use super::*;
+
+    fn visible(scrollback: &Scrollback) -> Vec<&str> {
+        scrollback
+            .visible()
+            .map(|line| line.text.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn splits_lines() {
+        let mut scrollback = Scrollback::default();
+        scrollback.push(Level::Info, "one\r\ntwo\n", None);
+        scrollback.push(Level::Warn, "", None);
+        assert_eq!(visible(&scrollback), vec!["one", "two", ""]);
+        assert_eq!(scrollback.lines()[2].level, Level::Warn);
+    }
+
+    #[test]
+    fn scrolling_keeps_view() {
+        let mut scrollback = Scrollback::default();
+        scrollback.set_height(2);
+        scrollback.push(Level::Info, "1\n2\n3\n4", Some(5));
+        assert_eq!(visible(&scrollback), vec!["3", "4"]);
+
+        assert!(scrollback.scroll_by(true, 1));
+        assert_eq!(visible(&scrollback), vec!["2", "3"]);
+        assert!(scrollback.scroll_by(true, 5));
+        assert_eq!(scrollback.scroll(), 2);
+        assert_eq!(visible(&scrollback), vec!["1", "2"]);
+
+        scrollback.push(Level::Info, "5", Some(5));
+        assert_eq!(scrollback.scroll(), 3);
+        assert_eq!(visible(&scrollback), vec!["1", "2"]);
+
+        scrollback.push(Level::Info, "6\n7", Some(5));
+        assert_eq!(scrollback.scroll(), 3);
+        assert_eq!(visible(&scrollback), vec!["3", "4"]);
+
+        assert!(scrollback.scroll_by(false, 10));
+        assert_eq!(visible(&scrollback), vec!["6", "7"]);
+        assert!(!scrollback.scroll_by(false, 1));
+    }
+}
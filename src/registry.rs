@@ -0,0 +1,350 @@
+//! A registry of named commands, as an alternative to parsing every entry
+//! into a single `FromStr` type.
+
+#[cfg(any(debug_assertions, feature = "force-enabled"))]
+use std::collections::BTreeMap;
+
+#[cfg(not(any(debug_assertions, feature = "force-enabled")))]
+use std::marker::PhantomData;
+
+use crate::args::{Args, Signature};
+use crate::error::Error;
+#[cfg(any(debug_assertions, feature = "force-enabled"))]
+use crate::error::ErrorKind;
+
+/// The result of running an entry through a `Registry`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    /// The entry was empty, or only whitespace.
+    Empty,
+
+    /// The command's handler ran, and produced this output.
+    Output(String),
+
+    /// The entry could not be tokenized, named an unknown command, did not
+    /// match the command's signature, or was rejected by its handler.
+    Failed(Error),
+}
+
+#[cfg(any(debug_assertions, feature = "force-enabled"))]
+type Handler<Ctx> = Box<dyn FnMut(Args, &mut Ctx) -> Result<String, Error>>;
+
+#[cfg(any(debug_assertions, feature = "force-enabled"))]
+struct Command<Ctx> {
+    signature: Option<Signature>,
+    handler: Handler<Ctx>,
+}
+
+/// A set of commands, each with a handler that is run when an entry starting
+/// with its name is confirmed.
+///
+/// Handlers take the arguments after the command name, and a mutable context
+/// of your choosing (usually your game or application state). Commands
+/// registered with a `Signature` have their arguments checked before the
+/// handler runs, and can look them up by name.
+///
+/// In release mode (unless the `force-enabled` feature is on), the registry
+/// stores nothing and handlers are dropped as soon as they are registered, so
+/// none of your debug commands are included in the final build.
+///
+/// # Examples
+///
+/// ```rust
+/// use dbgcmd::{Console, Outcome, Registry, Signature};
+///
+/// struct Game {
+///     gravity: f32,
+/// }
+///
+/// let mut registry = Registry::new();
+/// registry.register("gravity", |args, game: &mut Game| {
+///     game.gravity = args.parse(0)?;
+///     Ok(format!("gravity is now {}", game.gravity))
+/// });
+///
+/// registry.register_with(
+///     "fall",
+///     Signature::new().named::<f32>("speed").default_value("1.0"),
+///     |args, game: &mut Game| {
+///         let speed: f32 = args.value("speed")?;
+///         Ok(format!("falling at {}", speed * game.gravity))
+///     },
+/// );
+///
+/// let mut game = Game { gravity: 9.8 };
+/// let mut console = Console::new();
+/// console.set_entry("gravity 3.7".to_owned());
+///
+/// let outcome = console.confirm_command(&mut registry, &mut game);
+///
+/// if console.enabled() {
+///     assert_eq!(outcome, Outcome::Output("gravity is now 3.7".to_owned()));
+///     assert_eq!(game.gravity, 3.7);
+/// }
+///
+/// console.set_entry("fall --speed 2".to_owned());
+/// let outcome = console.confirm_command(&mut registry, &mut game);
+///
+/// if console.enabled() {
+///     assert_eq!(outcome, Outcome::Output("falling at 7.4".to_owned()));
+/// }
+/// ```
+#[cfg(any(debug_assertions, feature = "force-enabled"))]
+pub struct Registry<Ctx> {
+    commands: BTreeMap<String, Command<Ctx>>,
+}
+
+#[cfg(not(any(debug_assertions, feature = "force-enabled")))]
+pub struct Registry<Ctx> {
+    _ctx: PhantomData<fn(&mut Ctx)>,
+}
+
+impl<Ctx> Default for Registry<Ctx> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(any(debug_assertions, feature = "force-enabled"))]
+impl<Ctx> Registry<Ctx> {
+    pub fn new() -> Self {
+        Registry {
+            commands: BTreeMap::new(),
+        }
+    }
+
+    /// Registers a command, replacing any existing command with the same name.
+    pub fn register<F>(&mut self, name: &str, handler: F)
+    where
+        F: FnMut(Args, &mut Ctx) -> Result<String, Error> + 'static,
+    {
+        self.commands.insert(
+            name.to_owned(),
+            Command {
+                signature: None,
+                handler: Box::new(handler),
+            },
+        );
+    }
+
+    /// Registers a command whose arguments are matched against `signature`
+    /// before `handler` is called, replacing any existing command with the
+    /// same name.
+    pub fn register_with<F>(&mut self, name: &str, signature: Signature, handler: F)
+    where
+        F: FnMut(Args, &mut Ctx) -> Result<String, Error> + 'static,
+    {
+        self.commands.insert(
+            name.to_owned(),
+            Command {
+                signature: Some(signature),
+                handler: Box::new(handler),
+            },
+        );
+    }
+
+    /// Removes a command. Returns `true` if it was registered.
+    pub fn unregister(&mut self, name: &str) -> bool {
+        self.commands.remove(name).is_some()
+    }
+
+    /// Whether a command is registered with the given name.
+    pub fn contains(&self, name: &str) -> bool {
+        self.commands.contains_key(name)
+    }
+
+    /// Returns an iterator over the names of all registered commands, in
+    /// alphabetical order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.commands.keys().map(String::as_str)
+    }
+
+    /// Returns the signature a command was registered with, if any.
+    pub fn signature(&self, name: &str) -> Option<&Signature> {
+        self.commands.get(name)?.signature.as_ref()
+    }
+
+    /// Splits an entry into tokens, and runs the command named by the first
+    /// token with the rest as its arguments.
+    pub fn dispatch(&mut self, entry: &str, ctx: &mut Ctx) -> Outcome {
+        let tokens = match crate::tokenize(entry) {
+            Ok(tokens) => tokens,
+            Err(e) => return Outcome::Failed(e.into()),
+        };
+        let (name, args) = match tokens.split_first() {
+            Some((name, args)) => (name, args),
+            None => return Outcome::Empty,
+        };
+        let command = match self.commands.get_mut(&name.text) {
+            Some(command) => command,
+            None => {
+                return Outcome::Failed(Error::new(
+                    ErrorKind::UnknownCommand {
+                        name: name.text.clone(),
+                    },
+                    Some(name.span.clone()),
+                ))
+            }
+        };
+        let args = match &command.signature {
+            Some(signature) => match signature.parse(args) {
+                Ok(args) => args,
+                Err(e) => return Outcome::Failed(e.into()),
+            },
+            None => Args::new(args),
+        };
+        match (command.handler)(args, ctx) {
+            Ok(output) => Outcome::Output(output),
+            Err(error) => Outcome::Failed(error),
+        }
+    }
+}
+
+#[cfg(not(any(debug_assertions, feature = "force-enabled")))]
+impl<Ctx> Registry<Ctx> {
+    pub fn new() -> Self {
+        Registry { _ctx: PhantomData }
+    }
+
+    pub fn register<F>(&mut self, _name: &str, _handler: F)
+    where
+        F: FnMut(Args, &mut Ctx) -> Result<String, Error> + 'static,
+    {
+    }
+
+    pub fn register_with<F>(&mut self, _name: &str, _signature: Signature, _handler: F)
+    where
+        F: FnMut(Args, &mut Ctx) -> Result<String, Error> + 'static,
+    {
+    }
+
+    pub fn unregister(&mut self, _name: &str) -> bool {
+        false
+    }
+
+    pub fn contains(&self, _name: &str) -> bool {
+        false
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        std::iter::empty()
+    }
+
+    pub fn signature(&self, _name: &str) -> Option<&Signature> {
+        None
+    }
+
+    pub fn dispatch(&mut self, _entry: &str, _ctx: &mut Ctx) -> Outcome {
+        Outcome::Empty
+    }
+}
+
+#[cfg(test)]
+#[cfg(any(debug_assertions, feature = "force-enabled"))]
+mod tests {
+    use super::*;
+
+    fn failure(registry: &mut Registry<Vec<String>>, entry: &str) -> String {
+        match registry.dispatch(entry, &mut Vec::new()) {
+            Outcome::Failed(error) => error.render(entry),
+            outcome => panic!("expected a failure, got {:?}", outcome),
+        }
+    }
+
+    fn registry() -> Registry<Vec<String>> {
+        let mut registry = Registry::new();
+        registry.register("spawn", |args, spawned: &mut Vec<String>| {
+            let count: usize = args.parse(1)?;
+            for _ in 0..count {
+                spawned.push(args.parse(0)?);
+            }
+            Ok(format!("spawned {}", count))
+        });
+        registry.register("clear", |_, spawned: &mut Vec<String>| {
+            spawned.clear();
+            Ok(String::new())
+        });
+        registry.register_with(
+            "spawn-many",
+            Signature::new()
+                .rest::<String>("names")
+                .named::<usize>("count")
+                .short('n')
+                .default_value("1"),
+            |args, spawned: &mut Vec<String>| {
+                let count: usize = args.value("count")?;
+                for name in args.values::<String>("names")? {
+                    spawned.extend(std::iter::repeat_n(name, count));
+                }
+                Ok(String::new())
+            },
+        );
+        registry
+    }
+
+    #[test]
+    fn dispatches_by_name() {
+        let mut registry = registry();
+        let mut spawned = Vec::new();
+
+        assert_eq!(
+            registry.dispatch("spawn 'big orc' 2", &mut spawned),
+            Outcome::Output("spawned 2".into())
+        );
+        assert_eq!(spawned, vec!["big orc", "big orc"]);
+
+        registry.dispatch("clear", &mut spawned);
+        assert!(spawned.is_empty());
+
+        registry.dispatch("spawn-many orc imp -n 2", &mut spawned);
+        assert_eq!(spawned, vec!["orc", "orc", "imp", "imp"]);
+    }
+
+    #[test]
+    fn reports_failures() {
+        let mut registry = registry();
+        let mut spawned = Vec::new();
+
+        assert_eq!(registry.dispatch("  ", &mut spawned), Outcome::Empty);
+        assert_eq!(
+            failure(&mut registry, "fly"),
+            "fly\n^^^ unknown command \"fly\""
+        );
+        assert_eq!(
+            failure(&mut registry, "spawn orc"),
+            "spawn orc\n         ^ missing argument 2"
+        );
+        assert_eq!(
+            failure(&mut registry, "spawn orc x"),
+            "spawn orc x\n          ^ invalid value \"x\" for argument 2 (expected usize)"
+        );
+        assert_eq!(
+            failure(&mut registry, "spawn 'orc"),
+            "spawn 'orc\n      ^ unterminated ' quote"
+        );
+        assert_eq!(
+            failure(&mut registry, "spawn-many orc --count lots"),
+            "spawn-many orc --count lots\n\
+             \x20                      ^^^^ invalid value \"lots\" for argument count (expected usize)"
+        );
+    }
+
+    #[test]
+    fn names() {
+        let mut registry = registry();
+        assert_eq!(
+            registry.names().collect::<Vec<_>>(),
+            vec!["clear", "spawn", "spawn-many"]
+        );
+
+        assert!(registry.unregister("clear"));
+        assert!(!registry.unregister("clear"));
+        assert!(!registry.contains("clear"));
+        assert!(registry.contains("spawn"));
+        assert!(registry.signature("spawn").is_none());
+        assert_eq!(
+            registry.signature("spawn-many").unwrap().usage(),
+            "[names...] [--count <usize>]"
+        );
+    }
+}
@@ -0,0 +1,63 @@
+//! Reverse incremental search through the command history.
+
+use std::collections::{BTreeMap, VecDeque};
+
+use crate::fuzzy::fuzzy_match;
+use crate::undo::Snapshot;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Search {
+    pub query: String,
+    pub matched: Option<usize>,
+
+    /// The state from before the search started, restored if it is cancelled.
+    pub saved: Snapshot,
+}
+
+/// The history items as they are shown in the entry, with any edits made to
+/// them while browsing the history.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Items<'a> {
+    pub history: &'a VecDeque<String>,
+    pub edits: &'a BTreeMap<usize, String>,
+}
+
+impl<'a> Items<'a> {
+    pub fn len(&self) -> usize {
+        self.history.len()
+    }
+
+    pub fn get(&self, i: usize) -> &'a str {
+        self.edits.get(&i).unwrap_or(&self.history[i])
+    }
+}
+
+/// Whether `entry` matches `query`, either as a substring or, if `fuzzy` is
+/// set, as a subsequence scored by `fuzzy_match`.
+pub(crate) fn matches(entry: &str, query: &str, fuzzy: bool) -> bool {
+    if fuzzy {
+        fuzzy_match(query, entry).is_some()
+    } else {
+        entry.contains(query)
+    }
+}
+
+/// Finds the most recent history item matching `query`, starting at index
+/// `from` and moving towards older items.
+pub(crate) fn find_older(items: Items, query: &str, fuzzy: bool, from: usize) -> Option<usize> {
+    if query.is_empty() {
+        return None;
+    }
+    (from..items.len()).find(|&i| matches(items.get(i), query, fuzzy))
+}
+
+/// Finds the oldest history item matching `query` that is newer than the one
+/// at index `before`.
+pub(crate) fn find_newer(items: Items, query: &str, fuzzy: bool, before: usize) -> Option<usize> {
+    if query.is_empty() {
+        return None;
+    }
+    (0..before)
+        .rev()
+        .find(|&i| matches(items.get(i), query, fuzzy))
+}
@@ -0,0 +1,108 @@
+//! Suggestions of how to finish the entry, from the command history.
+
+use std::collections::VecDeque;
+
+/// Finds the most recent history entry that starts with `entry` and is longer
+/// than it, starting at index `from` and moving towards older entries.
+pub(crate) fn find(history: &VecDeque<String>, entry: &str, from: usize) -> Option<usize> {
+    if entry.is_empty() {
+        return None;
+    }
+    (from..history.len()).find(|&i| history[i].len() > entry.len() && history[i].starts_with(entry))
+}
+
+/// Remembers the suggestions found for each prefix of the entry typed so far.
+///
+/// Anything that extends the entry also extends every prefix of it, so the
+/// suggestion for a longer entry can never be more recent than the one for a
+/// shorter entry. When text is typed at the end, the search picks up from the
+/// previous suggestion instead of the newest history entry, and when text is
+/// removed from the end, the suggestion for the shorter entry is still here.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct Suggester {
+    entry: String,
+    found: Vec<(usize, Option<usize>)>,
+}
+
+impl Suggester {
+    /// Forgets every suggestion. This must be called whenever the history
+    /// changes, since the indices would no longer be valid.
+    pub fn clear(&mut self) {
+        self.entry.clear();
+        self.found.clear();
+    }
+
+    /// Returns the index of the suggested history entry for `entry`, or `None`
+    /// if the suggestions are not up to date with it.
+    pub fn get(&self, entry: &str) -> Option<Option<usize>> {
+        if entry.is_empty() {
+            return Some(None);
+        }
+        match self.found.last() {
+            Some(&(_, found)) if self.entry == entry => Some(found),
+            _ => None,
+        }
+    }
+
+    /// Brings the suggestions up to date with `entry`, reusing what was found
+    /// for the longest prefix it shares with the previous entry.
+    ///
+    /// The empty entry never has a suggestion, but that says nothing about
+    /// longer entries, so it isn't remembered.
+    pub fn update(&mut self, history: &VecDeque<String>, entry: &str) {
+        if entry.is_empty() {
+            self.clear();
+            return;
+        }
+        let common = self
+            .entry
+            .bytes()
+            .zip(entry.bytes())
+            .take_while(|(a, b)| a == b)
+            .count();
+        while self.found.last().is_some_and(|&(len, _)| len > common) {
+            self.found.pop();
+        }
+        self.entry = entry.to_owned();
+
+        let from = match self.found.last() {
+            Some(&(len, _)) if len == entry.len() => return,
+            Some(&(_, Some(from))) => from,
+            Some(&(_, None)) => {
+                self.found.push((entry.len(), None));
+                return;
+            }
+            None => 0,
+        };
+        self.found.push((entry.len(), find(history, entry, from)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn incremental_updates() {
+        let history: VecDeque<String> = vec!["spawn imp", "speed 2", "spawn orc"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+        let mut suggester = Suggester::default();
+
+        suggester.update(&history, "sp");
+        assert_eq!(suggester.get("sp"), Some(Some(0)));
+        suggester.update(&history, "spawn o");
+        assert_eq!(suggester.get("spawn o"), Some(Some(2)));
+        suggester.update(&history, "spawn orcs");
+        assert_eq!(suggester.get("spawn orcs"), Some(None));
+        assert_eq!(suggester.get("spawn"), None);
+
+        suggester.update(&history, "spawn ");
+        assert_eq!(suggester.get("spawn "), Some(Some(0)));
+        suggester.update(&history, "spe");
+        assert_eq!(suggester.get("spe"), Some(Some(1)));
+        suggester.update(&history, "");
+        assert_eq!(suggester.get(""), Some(None));
+    }
+}
@@ -0,0 +1,128 @@
+//! Helpers for working with positions inside the command entry.
+//!
+//! All positions are byte offsets into the entry, and are always kept on
+//! grapheme cluster boundaries so that the caret never lands inside a
+//! multi-byte character or a combining sequence.
+
+#[cfg(any(debug_assertions, feature = "force-enabled"))]
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Decides where words begin and end for word-wise movement and deletion.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WordBoundary {
+    /// Words are separated only by whitespace, so `a/b/c` is a single word.
+    #[default]
+    Whitespace,
+
+    /// Words are runs of alphanumeric characters and underscores. Any other
+    /// character separates words, so `a/b/c` is three words.
+    Punctuation,
+}
+
+impl WordBoundary {
+    /// Whether the given grapheme is part of a word.
+    pub fn is_word(self, grapheme: &str) -> bool {
+        let ch = match grapheme.chars().next() {
+            Some(ch) => ch,
+            None => return false,
+        };
+        match self {
+            WordBoundary::Whitespace => !ch.is_whitespace(),
+            WordBoundary::Punctuation => ch.is_alphanumeric() || ch == '_',
+        }
+    }
+}
+
+/// Returns the byte offset of the grapheme boundary before `pos`, or `pos`
+/// itself if it is at the start of the text.
+#[cfg(any(debug_assertions, feature = "force-enabled"))]
+pub(crate) fn prev_grapheme(text: &str, pos: usize) -> usize {
+    text[..pos]
+        .grapheme_indices(true)
+        .next_back()
+        .map_or(0, |(i, _)| i)
+}
+
+/// Returns the byte offset of the grapheme boundary after `pos`, or `pos`
+/// itself if it is at the end of the text.
+#[cfg(any(debug_assertions, feature = "force-enabled"))]
+pub(crate) fn next_grapheme(text: &str, pos: usize) -> usize {
+    text[pos..]
+        .graphemes(true)
+        .next()
+        .map_or(pos, |g| pos + g.len())
+}
+
+/// Returns the start of the word before `pos`, skipping over any separators
+/// between it and `pos`.
+#[cfg(any(debug_assertions, feature = "force-enabled"))]
+pub(crate) fn word_start(text: &str, pos: usize, boundary: WordBoundary) -> usize {
+    let mut start = pos;
+    let mut in_word = false;
+    for (i, grapheme) in text[..pos].grapheme_indices(true).rev() {
+        let word = boundary.is_word(grapheme);
+        if in_word && !word {
+            break;
+        }
+        in_word |= word;
+        start = i;
+    }
+    start
+}
+
+/// Returns the end of the word after `pos`, skipping over any separators
+/// between `pos` and it.
+#[cfg(any(debug_assertions, feature = "force-enabled"))]
+pub(crate) fn word_end(text: &str, pos: usize, boundary: WordBoundary) -> usize {
+    let mut end = pos;
+    let mut in_word = false;
+    for (i, grapheme) in text[pos..].grapheme_indices(true) {
+        let word = boundary.is_word(grapheme);
+        if in_word && !word {
+            break;
+        }
+        in_word |= word;
+        end = pos + i + grapheme.len();
+    }
+    end
+}
+
+#[cfg(test)]
+#[cfg(any(debug_assertions, feature = "force-enabled"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grapheme_steps() {
+        let text = "ae\u{301}ö";
+
+        assert_eq!(next_grapheme(text, 0), 1);
+        assert_eq!(next_grapheme(text, 1), 4);
+        assert_eq!(next_grapheme(text, 4), 6);
+        assert_eq!(next_grapheme(text, 6), 6);
+
+        assert_eq!(prev_grapheme(text, 6), 4);
+        assert_eq!(prev_grapheme(text, 4), 1);
+        assert_eq!(prev_grapheme(text, 1), 0);
+        assert_eq!(prev_grapheme(text, 0), 0);
+    }
+
+    #[test]
+    fn word_steps() {
+        let text = "load  maps/big_one.map";
+        let ws = WordBoundary::Whitespace;
+        let punct = WordBoundary::Punctuation;
+
+        assert_eq!(word_start(text, text.len(), ws), 6);
+        assert_eq!(word_start(text, 6, ws), 0);
+        assert_eq!(word_start(text, 0, ws), 0);
+        assert_eq!(word_end(text, 0, ws), 4);
+        assert_eq!(word_end(text, 4, ws), text.len());
+
+        assert_eq!(word_start(text, text.len(), punct), 19);
+        assert_eq!(word_start(text, 18, punct), 11);
+        assert_eq!(word_start(text, 11, punct), 6);
+        assert_eq!(word_end(text, 4, punct), 10);
+        assert_eq!(word_end(text, 10, punct), 18);
+    }
+}
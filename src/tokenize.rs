@@ -0,0 +1,248 @@
+//! Splitting entries into shell-like tokens.
+
+use std::fmt;
+use std::ops::Range;
+
+/// A single token of an entry, as split by `tokenize`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Token {
+    /// The text of the token, with quotes and escapes removed.
+    pub text: String,
+
+    /// The byte range of the token in the original entry, including any
+    /// quotes and escapes.
+    pub span: Range<usize>,
+}
+
+impl Token {
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+}
+
+/// An error from splitting an entry into tokens.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TokenizeError {
+    /// A quote was opened but never closed. `offset` is the byte offset of the
+    /// opening quote.
+    UnterminatedQuote { quote: char, offset: usize },
+
+    /// The entry ended with a backslash, so there was nothing for it to escape.
+    /// `offset` is the byte offset of the backslash.
+    TrailingBackslash { offset: usize },
+}
+
+impl TokenizeError {
+    /// The byte offset into the entry that the error refers to.
+    pub fn offset(&self) -> usize {
+        match *self {
+            TokenizeError::UnterminatedQuote { offset, .. } => offset,
+            TokenizeError::TrailingBackslash { offset } => offset,
+        }
+    }
+}
+
+impl fmt::Display for TokenizeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TokenizeError::UnterminatedQuote { quote, offset } => {
+                write!(f, "unterminated {} quote at offset {}", quote, offset)
+            }
+            TokenizeError::TrailingBackslash { offset } => {
+                write!(f, "trailing backslash at offset {}", offset)
+            }
+        }
+    }
+}
+
+impl std::error::Error for TokenizeError {}
+
+/// Splits an entry into tokens, much like a shell would.
+///
+/// - Tokens are separated by whitespace.
+/// - Text in single quotes is taken literally.
+/// - Text in double quotes is taken literally, except that `\"` and `\\` are
+///   escapes for `"` and `\`.
+/// - Outside of quotes, a backslash escapes the character after it.
+/// - Quoted and unquoted text with no whitespace between is joined into one
+///   token, so `a"b c"d` is the single token `ab cd`.
+///
+/// # Examples
+///
+/// ```rust
+/// use dbgcmd::tokenize;
+///
+/// let tokens = tokenize(r#"say "hello world" it\'s me"#).unwrap();
+/// let texts: Vec<&str> = tokens.iter().map(|token| token.as_str()).collect();
+///
+/// assert_eq!(texts, vec!["say", "hello world", "it's", "me"]);
+/// assert_eq!(tokens[1].span, 4..17);
+/// ```
+pub fn tokenize(input: &str) -> Result<Vec<Token>, TokenizeError> {
+    let mut tokens = Vec::new();
+    let mut chars = input.char_indices().peekable();
+
+    while let Some(&(start, ch)) = chars.peek() {
+        if ch.is_whitespace() {
+            chars.next();
+            continue;
+        }
+
+        let mut text = String::new();
+        let mut end = start;
+        while let Some(&(i, ch)) = chars.peek() {
+            if ch.is_whitespace() {
+                break;
+            }
+            chars.next();
+            match ch {
+                '\'' | '"' => {
+                    let mut closed = false;
+                    while let Some((_, inner)) = chars.next() {
+                        if inner == ch {
+                            closed = true;
+                            break;
+                        }
+                        if ch == '"' && inner == '\\' {
+                            if let Some(&(_, escaped @ ('"' | '\\'))) = chars.peek() {
+                                chars.next();
+                                text.push(escaped);
+                                continue;
+                            }
+                        }
+                        text.push(inner);
+                    }
+                    if !closed {
+                        return Err(TokenizeError::UnterminatedQuote {
+                            quote: ch,
+                            offset: i,
+                        });
+                    }
+                }
+                '\\' => match chars.next() {
+                    Some((_, escaped)) => text.push(escaped),
+                    None => return Err(TokenizeError::TrailingBackslash { offset: i }),
+                },
+                ch => text.push(ch),
+            }
+            end = chars.peek().map_or(input.len(), |&(j, _)| j);
+        }
+
+        tokens.push(Token {
+            text,
+            span: start..end,
+        });
+    }
+
+    Ok(tokens)
+}
+
+/// Escapes text with backslashes so that `tokenize` reads it back as a single
+/// token, even if more text is appended to it later.
+#[cfg(any(debug_assertions, feature = "force-enabled"))]
+pub(crate) fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for ch in text.chars() {
+        if ch.is_whitespace() || matches!(ch, '\'' | '"' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(ch);
+    }
+    escaped
+}
+
+/// Wraps text in double quotes if `tokenize` would otherwise not read it back
+/// as a single token.
+pub(crate) fn quote(text: &str) -> String {
+    let plain = !text.is_empty()
+        && !text
+            .chars()
+            .any(|ch| ch.is_whitespace() || matches!(ch, '\'' | '"' | '\\'));
+    if plain {
+        return text.to_owned();
+    }
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+    for ch in text.chars() {
+        if matches!(ch, '"' | '\\') {
+            quoted.push('\\');
+        }
+        quoted.push(ch);
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(input: &str) -> Vec<String> {
+        tokenize(input)
+            .unwrap()
+            .into_iter()
+            .map(|token| token.text)
+            .collect()
+    }
+
+    #[test]
+    fn splits_on_whitespace() {
+        assert_eq!(texts("  spawn  orc\t3 "), vec!["spawn", "orc", "3"]);
+        assert!(texts("   ").is_empty());
+    }
+
+    #[test]
+    fn quotes_and_escapes() {
+        assert_eq!(texts(r#"'a "b"' "c 'd'""#), vec![r#"a "b""#, "c 'd'"]);
+        assert_eq!(texts(r#""a\"b\\c\n""#), vec![r#"a"b\c\n"#]);
+        assert_eq!(texts(r"a\ b \'c"), vec!["a b", "'c"]);
+        assert_eq!(texts(r#"a"b c"d '' """#), vec!["ab cd", "", ""]);
+    }
+
+    #[test]
+    fn spans() {
+        let tokens = tokenize(r#"tp "x y"z  é"#).unwrap();
+        let spans: Vec<_> = tokens.iter().map(|token| token.span.clone()).collect();
+
+        assert_eq!(spans, vec![0..2, 3..9, 11..13]);
+    }
+
+    #[test]
+    #[cfg(any(debug_assertions, feature = "force-enabled"))]
+    fn escaping() {
+        let text = r#"it's a "big" \ orc"#;
+        assert_eq!(texts(&escape(text)), vec![text]);
+        assert_eq!(escape("plain"), "plain");
+    }
+
+    #[test]
+    fn quoting() {
+        for text in ["plain", "", "big orc", r#"say "hi" \ bye"#, "it's"] {
+            assert_eq!(texts(&quote(text)), vec![text]);
+        }
+        assert_eq!(quote("plain"), "plain");
+        assert_eq!(quote("big orc"), "\"big orc\"");
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            tokenize(r#"say "hi"#),
+            Err(TokenizeError::UnterminatedQuote {
+                quote: '"',
+                offset: 4
+            })
+        );
+        assert_eq!(
+            tokenize("say 'it"),
+            Err(TokenizeError::UnterminatedQuote {
+                quote: '\'',
+                offset: 4
+            })
+        );
+        assert_eq!(
+            tokenize(r"say \"),
+            Err(TokenizeError::TrailingBackslash { offset: 4 })
+        );
+    }
+}
@@ -0,0 +1,546 @@
+//! Capturing `tracing` events and spans for the output of a `Console`.
+//!
+//! Events can be recorded from any thread, so `ConsoleLayer` only collects
+//! them. Add it to your subscriber, attach a clone of it to your `Console`
+//! with `Console::set_trace_layer`, and call `Console::drain_trace` once a
+//! frame to move them into the output.
+
+use std::fmt;
+
+use tracing_core::{Level as TraceLevel, LevelFilter};
+
+#[cfg(any(debug_assertions, feature = "force-enabled"))]
+use std::collections::{HashMap, VecDeque};
+
+#[cfg(any(debug_assertions, feature = "force-enabled"))]
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+#[cfg(any(debug_assertions, feature = "force-enabled"))]
+use tracing_core::{
+    field::{Field, Visit},
+    span, Event, Metadata, Subscriber,
+};
+
+use tracing_subscriber::layer::Layer;
+
+#[cfg(any(debug_assertions, feature = "force-enabled"))]
+use tracing_subscriber::layer::Context;
+
+use crate::output::Level;
+
+impl From<TraceLevel> for Level {
+    fn from(level: TraceLevel) -> Self {
+        if level == TraceLevel::ERROR {
+            Level::Error
+        } else if level == TraceLevel::WARN {
+            Level::Warn
+        } else if level == TraceLevel::INFO {
+            Level::Info
+        } else if level == TraceLevel::DEBUG {
+            Level::Debug
+        } else {
+            Level::Trace
+        }
+    }
+}
+
+/// What a `TraceEvent` records.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TraceKind {
+    /// An event, such as from `tracing::info!`.
+    Event,
+
+    /// A span was entered.
+    Enter,
+
+    /// A span was exited.
+    Exit,
+}
+
+/// An event or span transition captured by a `ConsoleLayer`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TraceEvent {
+    pub kind: TraceKind,
+    pub level: TraceLevel,
+    pub target: String,
+
+    /// The message of an event, or the name of a span.
+    pub name: String,
+
+    /// The name of the span that an event happened in, if any.
+    pub span: Option<String>,
+
+    /// The fields of the event or span, in the order they were recorded, with
+    /// their values formatted.
+    pub fields: Vec<(String, String)>,
+}
+
+impl TraceEvent {
+    /// Returns the value of the field called `name`.
+    pub fn field(&self, name: &str) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|(field, _)| field == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Formats the event on a single line, with its fields as `key=value` pairs.
+/// Values that are empty or contain whitespace are quoted.
+///
+/// # Examples
+///
+/// ```rust
+/// use dbgcmd::{TraceEvent, TraceKind};
+///
+/// let event = TraceEvent {
+///     kind: TraceKind::Event,
+///     level: tracing_core::Level::WARN,
+///     target: "game::ai".to_owned(),
+///     name: "lost track".to_owned(),
+///     span: Some("think".to_owned()),
+///     fields: vec![
+///         ("entity".to_owned(), "42".to_owned()),
+///         ("state".to_owned(), "chasing player".to_owned()),
+///     ],
+/// };
+///
+/// assert_eq!(
+///     event.to_string(),
+///     "think: game::ai: lost track entity=42 state=\"chasing player\"",
+/// );
+/// ```
+impl fmt::Display for TraceEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(span) = &self.span {
+            write!(f, "{}: ", span)?;
+        }
+        write!(f, "{}: ", self.target)?;
+        match self.kind {
+            TraceKind::Event => write!(f, "{}", self.name)?,
+            TraceKind::Enter => write!(f, "-> {}", self.name)?,
+            TraceKind::Exit => write!(f, "<- {}", self.name)?,
+        }
+        for (name, value) in &self.fields {
+            if value.is_empty() || value.contains(char::is_whitespace) {
+                write!(f, " {}={:?}", name, value)?;
+            } else {
+                write!(f, " {}={}", name, value)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Which captured events are kept, by level, target, and fields.
+///
+/// The target matches that module and every module below it, so `game` also
+/// covers `game::ai`. Field conditions must all match.
+///
+/// # Examples
+///
+/// ```rust
+/// use dbgcmd::TraceFilter;
+/// use tracing_core::LevelFilter;
+///
+/// // Debug events from the AI about entity 42, and any that have an error
+/// let about_42 = TraceFilter::new(LevelFilter::DEBUG)
+///     .target("game::ai")
+///     .field("entity", "42");
+/// let errors = TraceFilter::default().has_field("error");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TraceFilter {
+    level: LevelFilter,
+    target: Option<String>,
+    fields: Vec<(String, Option<String>)>,
+}
+
+impl Default for TraceFilter {
+    fn default() -> Self {
+        TraceFilter::new(LevelFilter::INFO)
+    }
+}
+
+impl TraceFilter {
+    /// Creates a filter that keeps events at `level` or more severe.
+    pub fn new(level: LevelFilter) -> Self {
+        TraceFilter {
+            level,
+            target: None,
+            fields: Vec::new(),
+        }
+    }
+
+    pub fn level(&self) -> LevelFilter {
+        self.level
+    }
+
+    /// Only keeps events from `target` and the modules below it.
+    pub fn target(mut self, target: &str) -> Self {
+        self.target = Some(target.to_owned());
+        self
+    }
+
+    /// Only keeps events with a field called `name` whose formatted value is
+    /// `value`.
+    pub fn field(mut self, name: &str, value: &str) -> Self {
+        self.fields.push((name.to_owned(), Some(value.to_owned())));
+        self
+    }
+
+    /// Only keeps events with a field called `name`, whatever its value.
+    pub fn has_field(mut self, name: &str) -> Self {
+        self.fields.push((name.to_owned(), None));
+        self
+    }
+
+    /// Whether `event` passes the filter.
+    pub fn matches(&self, event: &TraceEvent) -> bool {
+        let target_matches = match &self.target {
+            Some(target) => event
+                .target
+                .strip_prefix(target.as_str())
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with("::")),
+            None => true,
+        };
+        let fields_match =
+            self.fields
+                .iter()
+                .all(|(name, value)| match (event.field(name), value) {
+                    (Some(found), Some(value)) => found == value,
+                    (found, None) => found.is_some(),
+                    (None, Some(_)) => false,
+                });
+        event.level <= self.level && target_matches && fields_match
+    }
+}
+
+/// Formats every field of a span or event as a string.
+#[cfg(any(debug_assertions, feature = "force-enabled"))]
+#[derive(Default)]
+struct FieldVisitor {
+    message: Option<String>,
+    fields: Vec<(String, String)>,
+}
+
+#[cfg(any(debug_assertions, feature = "force-enabled"))]
+impl FieldVisitor {
+    fn push(&mut self, field: &Field, value: String) {
+        if field.name() == "message" {
+            self.message = Some(value);
+        } else {
+            self.fields.push((field.name().to_owned(), value));
+        }
+    }
+}
+
+#[cfg(any(debug_assertions, feature = "force-enabled"))]
+impl Visit for FieldVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.push(field, format!("{:?}", value));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.push(field, value.to_owned());
+    }
+}
+
+#[cfg(any(debug_assertions, feature = "force-enabled"))]
+#[derive(Debug)]
+struct SpanInfo {
+    metadata: &'static Metadata<'static>,
+    fields: Vec<(String, String)>,
+}
+
+#[cfg(any(debug_assertions, feature = "force-enabled"))]
+#[derive(Debug)]
+struct Shared {
+    events: VecDeque<TraceEvent>,
+    capacity: usize,
+    filter: TraceFilter,
+    spans: HashMap<span::Id, SpanInfo>,
+}
+
+#[cfg(any(debug_assertions, feature = "force-enabled"))]
+impl Shared {
+    fn push(&mut self, event: TraceEvent) {
+        if !self.filter.matches(&event) {
+            return;
+        }
+        if self.events.len() >= self.capacity {
+            self.events.pop_front();
+        }
+        if self.capacity > 0 {
+            self.events.push_back(event);
+        }
+    }
+
+    fn span_event(&self, kind: TraceKind, id: &span::Id) -> Option<TraceEvent> {
+        let info = self.spans.get(id)?;
+        Some(TraceEvent {
+            kind,
+            level: *info.metadata.level(),
+            target: info.metadata.target().to_owned(),
+            name: info.metadata.name().to_owned(),
+            span: None,
+            fields: info.fields.clone(),
+        })
+    }
+}
+
+/// A `tracing_subscriber::Layer` that captures events, and spans being entered
+/// and exited, for a `Console`.
+///
+/// This is a cheap handle to a buffer shared between threads, so clone it to
+/// keep one for the `Console` before adding it to a subscriber. Only the
+/// newest `capacity` events are kept until they are drained.
+///
+/// In release mode, unless the `force-enabled` feature is on, this is an empty
+/// layer that captures nothing.
+///
+/// # Examples
+///
+/// ```rust
+/// use dbgcmd::{Console, ConsoleLayer};
+/// use tracing_subscriber::layer::SubscriberExt;
+///
+/// let layer = ConsoleLayer::new();
+/// let subscriber = tracing_subscriber::registry().with(layer.clone());
+///
+/// let mut console = Console::new();
+/// console.set_trace_layer(layer);
+///
+/// tracing::subscriber::with_default(subscriber, || {
+///     tracing::warn!(target: "game", frame = 12, "slow frame");
+/// });
+///
+/// // Once a frame, on the thread that owns the console
+/// console.drain_trace();
+///
+/// if console.enabled() {
+///     assert_eq!(console.output().next().unwrap().text, "game: slow frame frame=12");
+/// }
+/// ```
+#[derive(Debug, Clone)]
+#[cfg(any(debug_assertions, feature = "force-enabled"))]
+pub struct ConsoleLayer {
+    shared: Arc<Mutex<Shared>>,
+}
+
+#[cfg(any(debug_assertions, feature = "force-enabled"))]
+impl Default for ConsoleLayer {
+    fn default() -> Self {
+        ConsoleLayer::with_capacity(ConsoleLayer::DEFAULT_CAPACITY)
+    }
+}
+
+#[cfg(any(debug_assertions, feature = "force-enabled"))]
+impl PartialEq for ConsoleLayer {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.shared, &other.shared)
+    }
+}
+
+#[cfg(any(debug_assertions, feature = "force-enabled"))]
+impl Eq for ConsoleLayer {}
+
+#[cfg(any(debug_assertions, feature = "force-enabled"))]
+impl ConsoleLayer {
+    /// The number of events kept by `new` until they are drained.
+    pub const DEFAULT_CAPACITY: usize = 1024;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        ConsoleLayer {
+            shared: Arc::new(Mutex::new(Shared {
+                events: VecDeque::new(),
+                capacity,
+                filter: TraceFilter::default(),
+                spans: HashMap::new(),
+            })),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Shared> {
+        self.shared.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub fn filter(&self) -> TraceFilter {
+        self.lock().filter.clone()
+    }
+
+    /// Replaces the filter. Events that don't pass it are not captured.
+    pub fn set_filter(&self, filter: TraceFilter) {
+        self.lock().filter = filter;
+    }
+
+    /// Removes and returns every event captured so far, oldest first.
+    pub fn drain(&self) -> Vec<TraceEvent> {
+        self.lock().events.drain(..).collect()
+    }
+}
+
+#[cfg(any(debug_assertions, feature = "force-enabled"))]
+impl<S: Subscriber> Layer<S> for ConsoleLayer {
+    fn on_new_span(&self, attrs: &span::Attributes, id: &span::Id, _ctx: Context<S>) {
+        let mut visitor = FieldVisitor::default();
+        attrs.record(&mut visitor);
+        let info = SpanInfo {
+            metadata: attrs.metadata(),
+            fields: visitor.fields,
+        };
+        self.lock().spans.insert(id.clone(), info);
+    }
+
+    fn on_record(&self, id: &span::Id, values: &span::Record, _ctx: Context<S>) {
+        let mut visitor = FieldVisitor::default();
+        values.record(&mut visitor);
+        if let Some(info) = self.lock().spans.get_mut(id) {
+            info.fields.extend(visitor.fields);
+        }
+    }
+
+    fn on_event(&self, event: &Event, ctx: Context<S>) {
+        let mut visitor = FieldVisitor::default();
+        event.record(&mut visitor);
+
+        let parent = if event.is_contextual() {
+            ctx.current_span().id().cloned()
+        } else {
+            event.parent().cloned()
+        };
+        let mut shared = self.lock();
+        let span = parent
+            .and_then(|id| shared.spans.get(&id))
+            .map(|info| info.metadata.name().to_owned());
+        let metadata = event.metadata();
+        shared.push(TraceEvent {
+            kind: TraceKind::Event,
+            level: *metadata.level(),
+            target: metadata.target().to_owned(),
+            name: visitor.message.unwrap_or_default(),
+            span,
+            fields: visitor.fields,
+        });
+    }
+
+    fn on_enter(&self, id: &span::Id, _ctx: Context<S>) {
+        let mut shared = self.lock();
+        if let Some(event) = shared.span_event(TraceKind::Enter, id) {
+            shared.push(event);
+        }
+    }
+
+    fn on_exit(&self, id: &span::Id, _ctx: Context<S>) {
+        let mut shared = self.lock();
+        if let Some(event) = shared.span_event(TraceKind::Exit, id) {
+            shared.push(event);
+        }
+    }
+
+    fn on_close(&self, id: span::Id, _ctx: Context<S>) {
+        self.lock().spans.remove(&id);
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+#[cfg(not(any(debug_assertions, feature = "force-enabled")))]
+pub struct ConsoleLayer;
+
+#[cfg(not(any(debug_assertions, feature = "force-enabled")))]
+impl ConsoleLayer {
+    pub const DEFAULT_CAPACITY: usize = 1024;
+
+    pub fn new() -> Self {
+        ConsoleLayer
+    }
+
+    pub fn with_capacity(_capacity: usize) -> Self {
+        ConsoleLayer
+    }
+
+    pub fn filter(&self) -> TraceFilter {
+        TraceFilter::default()
+    }
+
+    pub fn set_filter(&self, _filter: TraceFilter) {}
+
+    pub fn drain(&self) -> Vec<TraceEvent> {
+        Vec::new()
+    }
+}
+
+#[cfg(not(any(debug_assertions, feature = "force-enabled")))]
+impl<S: tracing_core::Subscriber> Layer<S> for ConsoleLayer {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(level: TraceLevel, target: &str, fields: &[(&str, &str)]) -> TraceEvent {
+        TraceEvent {
+            kind: TraceKind::Event,
+            level,
+            target: target.to_owned(),
+            name: "message".to_owned(),
+            span: None,
+            fields: fields
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn filtering() {
+        let filter = TraceFilter::new(LevelFilter::DEBUG)
+            .target("game")
+            .field("entity", "42")
+            .has_field("state");
+
+        let fields = [("entity", "42"), ("state", "")];
+        assert!(filter.matches(&event(TraceLevel::DEBUG, "game::ai", &fields)));
+        assert!(!filter.matches(&event(TraceLevel::TRACE, "game::ai", &fields)));
+        assert!(!filter.matches(&event(TraceLevel::INFO, "gameplay", &fields)));
+        assert!(!filter.matches(&event(TraceLevel::INFO, "game", &fields[..1])));
+        assert!(!filter.matches(&event(
+            TraceLevel::INFO,
+            "game",
+            &[("entity", "7"), ("state", "")]
+        )));
+        assert!(TraceFilter::default().matches(&event(TraceLevel::INFO, "x", &[])));
+    }
+
+    #[cfg(any(debug_assertions, feature = "force-enabled"))]
+    #[test]
+    fn captures_events_and_spans() {
+        use tracing_subscriber::layer::SubscriberExt;
+
+        let layer = ConsoleLayer::with_capacity(4);
+        let subscriber = tracing_subscriber::registry().with(layer.clone());
+        tracing::subscriber::with_default(subscriber, || {
+            let span = tracing::info_span!(target: "game", "think", entity = 42);
+            let _guard = span.enter();
+            tracing::debug!(target: "game", "hidden");
+            tracing::info!(target: "game", state = "chasing player", "lost track");
+        });
+
+        let events = layer.drain();
+        let lines: Vec<String> = events.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            lines,
+            vec![
+                "game: -> think entity=42",
+                "think: game: lost track state=\"chasing player\"",
+                "game: <- think entity=42",
+            ]
+        );
+        assert_eq!(events[1].field("state"), Some("chasing player"));
+        assert!(layer.drain().is_empty());
+        assert!(layer.lock().spans.is_empty());
+    }
+}
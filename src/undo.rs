@@ -0,0 +1,104 @@
+//! The undo and redo stacks for the command entry.
+
+use std::collections::{BTreeMap, VecDeque};
+
+/// The default maximum number of undo steps kept by a `Console`.
+pub(crate) const DEFAULT_UNDO_LIMIT: usize = 100;
+
+/// The kind of an edit made to the entry, used to decide whether it can be
+/// merged into the previous undo step.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum EditKind {
+    Insert,
+    Navigate,
+    KillLeft,
+    KillRight,
+    Yank { start: usize, end: usize },
+    Complete,
+    Other,
+}
+
+impl EditKind {
+    /// Whether consecutive edits of this kind are undone together.
+    pub(crate) fn coalesces(self) -> bool {
+        matches!(
+            self,
+            EditKind::Insert | EditKind::Navigate | EditKind::Complete
+        )
+    }
+}
+
+/// The editable state of the console at a single point in time.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Snapshot {
+    pub entry: String,
+    pub edits: BTreeMap<usize, String>,
+    pub cursor: Option<usize>,
+    pub caret: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct UndoStack {
+    undo: VecDeque<Snapshot>,
+    redo: Vec<Snapshot>,
+    limit: usize,
+}
+
+impl Default for UndoStack {
+    fn default() -> Self {
+        UndoStack {
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+            limit: DEFAULT_UNDO_LIMIT,
+        }
+    }
+}
+
+impl UndoStack {
+    /// Records the state from before a new edit. This discards anything that
+    /// could previously have been redone.
+    pub fn push(&mut self, snapshot: Snapshot) {
+        self.redo.clear();
+        self.undo.push_back(snapshot);
+        self.truncate();
+    }
+
+    /// Steps back one edit, given the current state. Returns the state to
+    /// restore, if there is one.
+    pub fn undo(&mut self, current: Snapshot) -> Option<Snapshot> {
+        let snapshot = self.undo.pop_back()?;
+        self.redo.push(current);
+        Some(snapshot)
+    }
+
+    /// Steps forward one previously undone edit, given the current state.
+    /// Returns the state to restore, if there is one.
+    pub fn redo(&mut self, current: Snapshot) -> Option<Snapshot> {
+        let snapshot = self.redo.pop()?;
+        self.undo.push_back(current);
+        self.truncate();
+        Some(snapshot)
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    pub fn set_limit(&mut self, limit: usize) {
+        self.limit = limit;
+        self.truncate();
+        let excess = self.redo.len().saturating_sub(limit);
+        self.redo.drain(..excess);
+    }
+
+    fn truncate(&mut self) {
+        while self.undo.len() > self.limit {
+            self.undo.pop_front();
+        }
+    }
+}